@@ -0,0 +1,9 @@
+#!/bin/sh
+# Downloads Noto Sans JP (SIL Open Font License) into assets/, the first font
+# of the default font chain, which draws the kana and kanji of the decks. It is
+# the variable font, named the way Google Fonts names it, regular by default.
+set -e
+cd "$(dirname "$0")/assets"
+base=https://github.com/google/fonts/raw/main/ofl/notosansjp
+curl -fL -o NotoSansJP-VariableFont_wght.ttf "$base/NotoSansJP%5Bwght%5D.ttf"
+curl -fL -o NotoSansJP-OFL.txt "$base/OFL.txt"
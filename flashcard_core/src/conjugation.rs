@@ -0,0 +1,502 @@
+use crate::Category;
+
+/// How a verb or adjective inflects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordClass {
+    Ichidan,
+    Godan,
+    /// する, 来る and nouns used with する.
+    Irregular,
+    IAdjective,
+    NaAdjective,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    Masu,
+    Te,
+    Ta,
+    Nai,
+    Potential,
+    Volitional,
+}
+
+impl Form {
+    pub const VERB: [Form; 6] = [
+        Form::Masu,
+        Form::Te,
+        Form::Ta,
+        Form::Nai,
+        Form::Potential,
+        Form::Volitional,
+    ];
+    pub const ADJECTIVE: [Form; 3] = [Form::Te, Form::Ta, Form::Nai];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Form::Masu => "masu-form",
+            Form::Te => "te-form",
+            Form::Ta => "ta-form",
+            Form::Nai => "nai-form",
+            Form::Potential => "potential",
+            Form::Volitional => "volitional",
+        }
+    }
+}
+
+impl WordClass {
+    pub fn forms(&self) -> &'static [Form] {
+        match self {
+            WordClass::Ichidan | WordClass::Godan | WordClass::Irregular => &Form::VERB,
+            WordClass::IAdjective | WordClass::NaAdjective => &Form::ADJECTIVE,
+        }
+    }
+}
+
+/// Godan verbs that end in いる/える and would otherwise look like ichidan verbs.
+const GODAN_EXCEPTIONS: [&str; 16] = [
+    "帰る",
+    "入る",
+    "走る",
+    "知る",
+    "要る",
+    "切る",
+    "限る",
+    "減る",
+    "照る",
+    "蹴る",
+    "滑る",
+    "握る",
+    "参る",
+    "混じる",
+    "焦る",
+    "喋る",
+];
+
+/// Na-adjectives ending in kana, which would otherwise be taken for
+/// i-adjectives or left out. Those ending in か like 静か are told apart by that.
+const NA_ADJECTIVES: [&str; 23] = [
+    "綺麗",
+    "きれい",
+    "嫌い",
+    "きらい",
+    "大嫌い",
+    "だいきらい",
+    "好き",
+    "すき",
+    "大好き",
+    "だいすき",
+    "すてき",
+    "ゆうめい",
+    "ていねい",
+    "しつれい",
+    "とくい",
+    "げんき",
+    "ひま",
+    "べんり",
+    "じょうず",
+    "へた",
+    "だめ",
+    "いや",
+    "たいへん",
+];
+
+/// Works out how `word` conjugates, using its kana `reading` to tell ichidan
+/// and godan verbs apart. Returns `None` for words that are not in dictionary
+/// form or whose category does not conjugate.
+pub fn classify(word: &str, reading: &str, category: Category) -> Option<WordClass> {
+    let last = word.chars().last()?;
+    let reading = if reading.is_empty() { word } else { reading };
+    match category {
+        Category::SuruVerb => Some(WordClass::Irregular),
+        Category::Verb => {
+            if word.ends_with("する") || reading == "くる" {
+                return Some(WordClass::Irregular);
+            }
+            if word.ends_with("ます") || godan_row(last).is_none() {
+                return None;
+            }
+            let before = reading.chars().rev().nth(1);
+            let ichidan = last == 'る'
+                && before.is_some_and(is_i_or_e_row)
+                && !GODAN_EXCEPTIONS.contains(&word);
+            Some(if ichidan {
+                WordClass::Ichidan
+            } else {
+                WordClass::Godan
+            })
+        }
+        Category::Adjective => {
+            if NA_ADJECTIVES.contains(&word) || last == 'か' || !is_kana(last) {
+                Some(WordClass::NaAdjective)
+            } else if last == 'い' && !word.ends_with("ている") {
+                Some(WordClass::IAdjective)
+            } else {
+                // adverbs like とても or 速く that the deck files as adjectives
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Conjugates a dictionary-form `word` into `form`.
+pub fn conjugate(word: &str, class: WordClass, form: Form) -> Option<String> {
+    let (stem, last) = split_last(word)?;
+    let conjugated = match class {
+        WordClass::Ichidan => {
+            let ending = match form {
+                Form::Masu => "ます",
+                Form::Te => "て",
+                Form::Ta => "た",
+                Form::Nai => "ない",
+                Form::Potential => "られる",
+                Form::Volitional => "よう",
+            };
+            format!("{}{}", stem, ending)
+        }
+        WordClass::Godan => {
+            let row = godan_row(last)?;
+            match form {
+                Form::Masu => format!("{}{}ます", stem, row[1]),
+                Form::Te | Form::Ta => {
+                    let (te, ta) = if word == "行く" || word == "いく" {
+                        ("って", "った")
+                    } else {
+                        te_ending(last)?
+                    };
+                    format!("{}{}", stem, if form == Form::Te { te } else { ta })
+                }
+                Form::Nai if word == "ある" => String::from("ない"),
+                Form::Nai => format!("{}{}ない", stem, row[0]),
+                Form::Potential => format!("{}{}る", stem, row[3]),
+                Form::Volitional => format!("{}{}う", stem, row[4]),
+            }
+        }
+        WordClass::Irregular => {
+            if word == "来る" || word == "くる" {
+                let (ki, ko) = if word == "来る" {
+                    ("来", "来")
+                } else {
+                    ("き", "こ")
+                };
+                match form {
+                    Form::Masu => format!("{}ます", ki),
+                    Form::Te => format!("{}て", ki),
+                    Form::Ta => format!("{}た", ki),
+                    Form::Nai => format!("{}ない", ko),
+                    Form::Potential => format!("{}られる", ko),
+                    Form::Volitional => format!("{}よう", ko),
+                }
+            } else {
+                let stem = word.strip_suffix("する").unwrap_or(word);
+                let ending = match form {
+                    Form::Masu => "します",
+                    Form::Te => "して",
+                    Form::Ta => "した",
+                    Form::Nai => "しない",
+                    Form::Potential => "できる",
+                    Form::Volitional => "しよう",
+                };
+                format!("{}{}", stem, ending)
+            }
+        }
+        WordClass::IAdjective => {
+            // いい conjugates from its older form よい
+            let stem = match word.strip_suffix("いい") {
+                Some(prefix) => format!("{}よ", prefix),
+                None => stem.to_owned(),
+            };
+            let ending = match form {
+                Form::Te => "くて",
+                Form::Ta => "かった",
+                Form::Nai => "くない",
+                _ => return None,
+            };
+            format!("{}{}", stem, ending)
+        }
+        WordClass::NaAdjective => {
+            let ending = match form {
+                Form::Te => "で",
+                Form::Ta => "だった",
+                Form::Nai => "じゃない",
+                _ => return None,
+            };
+            format!("{}{}", word, ending)
+        }
+    };
+    Some(conjugated)
+}
+
+/// A wrong answer a learner could plausibly pick: the word conjugated as if it
+/// belonged to a neighbouring class, or failing that a different form of it.
+pub fn lure(word: &str, class: WordClass, form: Form) -> Option<String> {
+    let correct = conjugate(word, class, form)?;
+    let (stem, last) = split_last(word)?;
+
+    let confused = match class {
+        WordClass::Ichidan => conjugate(word, WordClass::Godan, form),
+        WordClass::Godan if last == 'る' => conjugate(word, WordClass::Ichidan, form),
+        WordClass::Godan => {
+            let row = godan_row(last)?;
+            match form {
+                Form::Masu => Some(format!("{}{}ます", stem, row[0])),
+                Form::Te | Form::Ta => {
+                    // swap in the te-ending of another godan group, e.g. 書く => 書って
+                    let other = match last {
+                        'く' if word == "行く" || word == "いく" => 'く',
+                        'う' | 'つ' => 'む',
+                        'む' | 'ぶ' | 'ぬ' => 'く',
+                        _ => 'う',
+                    };
+                    let (te, ta) = te_ending(other)?;
+                    Some(format!(
+                        "{}{}",
+                        stem,
+                        if form == Form::Te { te } else { ta }
+                    ))
+                }
+                Form::Nai => Some(format!("{}{}ない", stem, row[1])),
+                Form::Potential => Some(format!("{}{}れる", stem, row[0])),
+                Form::Volitional => Some(format!("{}{}よう", stem, row[1])),
+            }
+        }
+        WordClass::Irregular if word == "来る" || word == "くる" => {
+            conjugate(word, WordClass::Godan, form)
+        }
+        WordClass::Irregular => {
+            // する treated like an ichidan verb, e.g. 勉強すて
+            let suru = format!("{}する", word.strip_suffix("する").unwrap_or(word));
+            conjugate(&suru, WordClass::Ichidan, form)
+        }
+        WordClass::IAdjective => conjugate(word, WordClass::NaAdjective, form),
+        WordClass::NaAdjective => conjugate(&format!("{}い", word), WordClass::IAdjective, form),
+    };
+
+    confused.filter(|lure| *lure != correct).or_else(|| {
+        class
+            .forms()
+            .iter()
+            .filter(|other| **other != form)
+            .filter_map(|other| conjugate(word, class, *other))
+            .find(|lure| *lure != correct)
+    })
+}
+
+fn split_last(word: &str) -> Option<(&str, char)> {
+    let last = word.chars().last()?;
+    Some((&word[..word.len() - last.len_utf8()], last))
+}
+
+/// The a, i, u, e and o row kana for a godan verb ending.
+fn godan_row(last: char) -> Option<[char; 5]> {
+    Some(match last {
+        'う' => ['わ', 'い', 'う', 'え', 'お'],
+        'く' => ['か', 'き', 'く', 'け', 'こ'],
+        'ぐ' => ['が', 'ぎ', 'ぐ', 'げ', 'ご'],
+        'す' => ['さ', 'し', 'す', 'せ', 'そ'],
+        'つ' => ['た', 'ち', 'つ', 'て', 'と'],
+        'ぬ' => ['な', 'に', 'ぬ', 'ね', 'の'],
+        'ぶ' => ['ば', 'び', 'ぶ', 'べ', 'ぼ'],
+        'む' => ['ま', 'み', 'む', 'め', 'も'],
+        'る' => ['ら', 'り', 'る', 'れ', 'ろ'],
+        _ => return None,
+    })
+}
+
+fn te_ending(last: char) -> Option<(&'static str, &'static str)> {
+    Some(match last {
+        'う' | 'つ' | 'る' => ("って", "った"),
+        'む' | 'ぶ' | 'ぬ' => ("んで", "んだ"),
+        'く' => ("いて", "いた"),
+        'ぐ' => ("いで", "いだ"),
+        'す' => ("して", "した"),
+        _ => return None,
+    })
+}
+
+fn is_i_or_e_row(kana: char) -> bool {
+    "いきぎしじちぢにひびぴみりえけげせぜてでねへべぺめれ".contains(kana)
+}
+
+pub fn is_kana(c: char) -> bool {
+    ('\u{3040}'..='\u{30ff}').contains(&c)
+}
+
+/// The hiragana for a katakana character, other characters as they are.
+pub(crate) fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms(word: &str, reading: &str, category: Category) -> Vec<String> {
+        let class = classify(word, reading, category).unwrap();
+        class
+            .forms()
+            .iter()
+            .map(|form| conjugate(word, class, *form).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn godan_verbs() {
+        assert_eq!(
+            forms("書く", "かく", Category::Verb),
+            [
+                "書きます",
+                "書いて",
+                "書いた",
+                "書かない",
+                "書ける",
+                "書こう"
+            ]
+        );
+        assert_eq!(
+            forms("飲む", "のむ", Category::Verb),
+            [
+                "飲みます",
+                "飲んで",
+                "飲んだ",
+                "飲まない",
+                "飲める",
+                "飲もう"
+            ]
+        );
+        assert_eq!(
+            forms("話す", "はなす", Category::Verb)[1..3],
+            ["話して", "話した"]
+        );
+        assert_eq!(forms("待つ", "まつ", Category::Verb)[1], "待って");
+        assert_eq!(forms("会う", "あう", Category::Verb)[3], "会わない");
+    }
+
+    #[test]
+    fn godan_verbs_that_look_like_ichidan() {
+        assert_eq!(
+            classify("帰る", "かえる", Category::Verb),
+            Some(WordClass::Godan)
+        );
+        assert_eq!(forms("帰る", "かえる", Category::Verb)[1], "帰って");
+        assert_eq!(forms("知る", "しる", Category::Verb)[3], "知らない");
+    }
+
+    #[test]
+    fn ichidan_verbs() {
+        assert_eq!(
+            forms("食べる", "たべる", Category::Verb),
+            [
+                "食べます",
+                "食べて",
+                "食べた",
+                "食べない",
+                "食べられる",
+                "食べよう"
+            ]
+        );
+        assert_eq!(
+            classify("見る", "みる", Category::Verb),
+            Some(WordClass::Ichidan)
+        );
+    }
+
+    #[test]
+    fn irregular_verbs() {
+        assert_eq!(
+            forms("する", "する", Category::Verb),
+            ["します", "して", "した", "しない", "できる", "しよう"]
+        );
+        assert_eq!(
+            forms("勉強", "べんきょう", Category::SuruVerb)[1],
+            "勉強して"
+        );
+        assert_eq!(
+            forms("来る", "くる", Category::Verb),
+            ["来ます", "来て", "来た", "来ない", "来られる", "来よう"]
+        );
+        assert_eq!(forms("くる", "くる", Category::Verb)[3], "こない");
+    }
+
+    #[test]
+    fn verb_exceptions() {
+        assert_eq!(
+            forms("行く", "いく", Category::Verb)[1..3],
+            ["行って", "行った"]
+        );
+        assert_eq!(forms("ある", "ある", Category::Verb)[3], "ない");
+        assert_eq!(classify("食べます", "たべます", Category::Verb), None);
+    }
+
+    #[test]
+    fn i_adjectives() {
+        assert_eq!(
+            forms("高い", "たかい", Category::Adjective),
+            ["高くて", "高かった", "高くない"]
+        );
+        assert_eq!(
+            forms("いい", "いい", Category::Adjective),
+            ["よくて", "よかった", "よくない"]
+        );
+    }
+
+    #[test]
+    fn na_adjectives() {
+        assert_eq!(
+            forms("元気", "げんき", Category::Adjective),
+            ["元気で", "元気だった", "元気じゃない"]
+        );
+        for word in [
+            "静か",
+            "賑やか",
+            "しずか",
+            "好き",
+            "きれい",
+            "嫌い",
+            "げんき",
+        ] {
+            assert_eq!(
+                classify(word, "", Category::Adjective),
+                Some(WordClass::NaAdjective),
+                "{}",
+                word
+            );
+        }
+        assert_eq!(
+            forms("好き", "すき", Category::Adjective)[2],
+            "好きじゃない"
+        );
+    }
+
+    #[test]
+    fn adverbs_filed_as_adjectives_are_left_out() {
+        assert_eq!(classify("とても", "とても", Category::Adjective), None);
+        assert_eq!(classify("速く", "はやく", Category::Adjective), None);
+        assert_eq!(
+            classify("合っている", "あっている", Category::Adjective),
+            None
+        );
+    }
+
+    #[test]
+    fn lures_differ_from_the_answer() {
+        for (word, reading, category) in [
+            ("食べる", "たべる", Category::Verb),
+            ("書く", "かく", Category::Verb),
+            ("する", "する", Category::Verb),
+            ("高い", "たかい", Category::Adjective),
+            ("静か", "しずか", Category::Adjective),
+        ] {
+            let class = classify(word, reading, category).unwrap();
+            for form in class.forms() {
+                let correct = conjugate(word, class, *form);
+                let lure = lure(word, class, *form);
+                assert!(lure.is_some() && lure != correct, "{} {:?}", word, form);
+            }
+        }
+    }
+}
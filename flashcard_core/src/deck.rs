@@ -0,0 +1,467 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use crate::conjugation::{classify, conjugate, lure};
+use crate::kanji::parse_kanji;
+use crate::pronunciation::KanaClips;
+use crate::Card;
+
+/// Where `load_cards` reads the deck files from, relative to the working
+/// directory. The game loads them as assets instead.
+pub const DECK_DIR: &str = "assets/decks";
+
+/// Every deck file the decks are built from.
+pub const DECK_FILES: [&str; 7] = [
+    "N5_translations_furigana.deck.toml",
+    "translations_furigana.deck.toml",
+    "hiragana.deck.toml",
+    "hiragana_dakuten.deck.toml",
+    "hiragana_handakuken.deck.toml",
+    "jlpt_vocab.deck.toml",
+    "kanji.deck.toml",
+];
+
+/// A dictionary that cards can be drawn from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckChoice {
+    Hiragana,
+    Vocabulary,
+    Jlpt,
+    Kanji,
+}
+
+impl std::str::FromStr for DeckChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hiragana" => Ok(DeckChoice::Hiragana),
+            "vocab" | "vocabulary" => Ok(DeckChoice::Vocabulary),
+            "jlpt" => Ok(DeckChoice::Jlpt),
+            "kanji" => Ok(DeckChoice::Kanji),
+            _ => Err(format!(
+                "unknown deck \"{}\" (expected hiragana, vocab, jlpt or kanji)",
+                s
+            )),
+        }
+    }
+}
+
+/// How cards are asked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionMode {
+    /// The word on the prompt, its translation as one of the choices.
+    Recall,
+    /// A verb or adjective and a form to put it in, its conjugation as one of the choices.
+    Conjugation,
+    /// A kanji on the prompt, its meaning as one of the choices.
+    KanjiMeaning,
+    /// A kanji on the prompt, its on'yomi and kun'yomi as one of the choices.
+    KanjiReading,
+}
+
+impl SessionMode {
+    /// Decks studied when none are picked.
+    pub fn default_decks(&self) -> Vec<DeckChoice> {
+        match self {
+            SessionMode::Recall => vec![DeckChoice::Hiragana],
+            // kana cards have nothing to conjugate
+            SessionMode::Conjugation => vec![DeckChoice::Vocabulary],
+            SessionMode::KanjiMeaning | SessionMode::KanjiReading => vec![DeckChoice::Kanji],
+        }
+    }
+}
+
+impl std::str::FromStr for SessionMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "recall" => Ok(SessionMode::Recall),
+            "conjugation" => Ok(SessionMode::Conjugation),
+            "kanji-meaning" => Ok(SessionMode::KanjiMeaning),
+            "kanji-reading" => Ok(SessionMode::KanjiReading),
+            _ => Err(format!(
+                "unknown mode \"{}\" (expected recall, conjugation, kanji-meaning or kanji-reading)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Adjective,
+    Adverb,
+    Noun,
+    Pronoun, // Add this line
+    Verb,
+    Time,
+    Question,
+    Response,
+    Conjunction,       // e.g., "and," "but"
+    Interjection,      // e.g., "yes," "no," "thank you"
+    PreNounAdjectival, // modifiers before nouns, like "that"
+    SuruVerb,          // nouns that can be used with "suru" to make verbs (e.g., 勉強する)
+}
+
+impl Category {
+    /// Short lowercase name shown on the prompt.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Adjective => "adjective",
+            Category::Adverb => "adverb",
+            Category::Noun => "noun",
+            Category::Pronoun => "pronoun",
+            Category::Verb => "verb",
+            Category::Time => "time",
+            Category::Question => "question",
+            Category::Response => "response",
+            Category::Conjunction => "conjunction",
+            Category::Interjection => "interjection",
+            Category::PreNounAdjectival => "pre-noun adjectival",
+            Category::SuruVerb => "suru verb",
+        }
+    }
+}
+
+impl std::str::FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s
+            .trim()
+            .to_ascii_lowercase()
+            .replace(['-', '_', ' '], "")
+            .as_str()
+        {
+            "adjective" => Ok(Category::Adjective),
+            "adverb" => Ok(Category::Adverb),
+            "noun" => Ok(Category::Noun),
+            "pronoun" => Ok(Category::Pronoun),
+            "verb" => Ok(Category::Verb),
+            "time" => Ok(Category::Time),
+            "question" => Ok(Category::Question),
+            "response" => Ok(Category::Response),
+            "conjunction" => Ok(Category::Conjunction),
+            "interjection" => Ok(Category::Interjection),
+            "prenounadjectival" => Ok(Category::PreNounAdjectival),
+            "suruverb" => Ok(Category::SuruVerb),
+            _ => Err(format!("unknown category \"{}\"", s)),
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+struct FullWord {
+    japanese_word: String,
+    furigana: String,
+    english_translation: String,
+    category: Category,
+    romaji: String,
+    #[serde(default)]
+    audio: Option<String>,
+}
+
+/// JLPT levels ordered from easiest (N5) to hardest (N1).
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum JlptLevel {
+    N5,
+    N4,
+    N3,
+    N2,
+    N1,
+}
+
+impl JlptLevel {
+    pub const ALL: [JlptLevel; 5] = [
+        JlptLevel::N5,
+        JlptLevel::N4,
+        JlptLevel::N3,
+        JlptLevel::N2,
+        JlptLevel::N1,
+    ];
+}
+
+impl std::str::FromStr for JlptLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_uppercase().as_str() {
+            "N5" => Ok(JlptLevel::N5),
+            "N4" => Ok(JlptLevel::N4),
+            "N3" => Ok(JlptLevel::N3),
+            "N2" => Ok(JlptLevel::N2),
+            "N1" => Ok(JlptLevel::N1),
+            _ => Err(format!("unknown JLPT level \"{}\" (expected N5..N1)", s)),
+        }
+    }
+}
+
+/// Parses a comma separated list of levels, where each item is either a single
+/// level (`N3`) or an inclusive range (`N5..N4`).
+pub fn parse_levels(value: &str) -> Result<Vec<JlptLevel>, String> {
+    let mut levels = Vec::new();
+    for item in value.split(',') {
+        if let Some((from, to)) = item.split_once("..") {
+            let (from, to): (JlptLevel, JlptLevel) = (from.parse()?, to.parse()?);
+            let (low, high) = (from.min(to), from.max(to));
+            levels.extend(
+                JlptLevel::ALL
+                    .iter()
+                    .filter(|level| (low..=high).contains(level)),
+            );
+        } else {
+            levels.push(item.parse()?);
+        }
+    }
+    levels.sort();
+    levels.dedup();
+    Ok(levels)
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+struct JlptWord {
+    original: String,
+    furigana: String,
+    english: String,
+    jlpt_level: JlptLevel,
+    #[serde(default)]
+    audio: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vocabulary {
+    translations: Vec<FullWord>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+struct Hiragana {
+    character: String,
+    romaji: String,
+    #[serde(default)]
+    audio: Option<String>,
+}
+
+/// Collects the cards of every deck in `decks`. Levels filter the JLPT and
+/// kanji decks, and an empty `categories` keeps every card.
+pub fn load_cards(
+    decks: &[DeckChoice],
+    jlpt_levels: &[JlptLevel],
+    categories: &[Category],
+    mode: SessionMode,
+) -> Result<Vec<Card>, String> {
+    parse_cards(decks, jlpt_levels, categories, mode, |file_name| {
+        let path = Path::new(DECK_DIR).join(file_name);
+        fs::read_to_string(&path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))
+    })
+}
+
+/// Like `load_cards`, with the contents of each file in `DECK_FILES` coming
+/// from `read` instead of the deck directory.
+pub fn parse_cards(
+    decks: &[DeckChoice],
+    jlpt_levels: &[JlptLevel],
+    categories: &[Category],
+    mode: SessionMode,
+    read: impl Fn(&str) -> Result<String, String>,
+) -> Result<Vec<Card>, String> {
+    let mut cards = Vec::new();
+
+    let mut vocabulary = parse_file(
+        &read,
+        "N5_translations_furigana.deck.toml",
+        parse_translations,
+    )?;
+    let extra_vocabulary =
+        parse_file(&read, "translations_furigana.deck.toml", parse_translations)?;
+    vocabulary
+        .translations
+        .extend(extra_vocabulary.translations);
+
+    for deck in decks {
+        match deck {
+            DeckChoice::Hiragana => {
+                let mut hiragana_list = parse_file(&read, "hiragana.deck.toml", parse_hiragana)?;
+                hiragana_list.extend(parse_file(
+                    &read,
+                    "hiragana_dakuten.deck.toml",
+                    parse_hiragana,
+                )?);
+                hiragana_list.extend(parse_file(
+                    &read,
+                    "hiragana_handakuken.deck.toml",
+                    parse_hiragana,
+                )?);
+
+                for hiragana in hiragana_list {
+                    cards.push(Card {
+                        word: hiragana.character.clone(),
+                        translation: hiragana.romaji.clone(),
+                        category: None,
+                        reading: None,
+                        lure: None,
+                        audio: hiragana.audio,
+                    });
+                }
+            }
+            DeckChoice::Vocabulary => {
+                for translation in &vocabulary.translations {
+                    cards.push(Card {
+                        word: translation.japanese_word.clone(),
+                        translation: translation.english_translation.clone(),
+                        category: Some(translation.category),
+                        reading: Some(translation.furigana.clone()),
+                        lure: None,
+                        audio: translation.audio.clone(),
+                    });
+                }
+            }
+            DeckChoice::Jlpt => {
+                let vocabulary_full = parse_file(&read, "jlpt_vocab.deck.toml", parse_jlpt_words)?;
+
+                // the jlpt deck has no categories of its own, so borrow them from the
+                // vocabulary deck wherever the same word appears there
+                let word_categories: HashMap<&str, Category> = vocabulary
+                    .translations
+                    .iter()
+                    .map(|word| (word.japanese_word.as_str(), word.category))
+                    .collect();
+
+                for translation in vocabulary_full
+                    .into_iter()
+                    .filter(|word| jlpt_levels.contains(&word.jlpt_level))
+                {
+                    cards.push(Card {
+                        category: word_categories.get(translation.original.as_str()).copied(),
+                        word: translation.original,
+                        translation: translation.english,
+                        reading: Some(translation.furigana),
+                        lure: None,
+                        audio: translation.audio,
+                    });
+                }
+            }
+            DeckChoice::Kanji => {
+                for kanji in parse_file(&read, "kanji.deck.toml", parse_kanji)?
+                    .into_iter()
+                    .filter(|kanji| jlpt_levels.contains(&kanji.jlpt_level))
+                {
+                    cards.push(Card {
+                        translation: match mode {
+                            SessionMode::KanjiReading => kanji.readings(),
+                            _ => kanji.meaning(),
+                        },
+                        word: kanji.character,
+                        category: None,
+                        reading: None,
+                        lure: None,
+                        audio: None,
+                    });
+                }
+            }
+        }
+    }
+
+    if !categories.is_empty() {
+        cards.retain(|card| {
+            card.category
+                .is_some_and(|category| categories.contains(&category))
+        });
+    }
+
+    if mode == SessionMode::Conjugation {
+        cards = cards.iter().flat_map(conjugation_cards).collect();
+    }
+
+    Ok(cards)
+}
+
+/// Turns a verb or adjective into one drill card per form it conjugates into,
+/// e.g. "食べる → te-form" answered by 食べて.
+fn conjugation_cards(card: &Card) -> Vec<Card> {
+    let Some(class) = card.category.and_then(|category| {
+        classify(
+            &card.word,
+            card.reading.as_deref().unwrap_or_default(),
+            category,
+        )
+    }) else {
+        return Vec::new();
+    };
+
+    class
+        .forms()
+        .iter()
+        .filter_map(|form| {
+            Some(Card {
+                word: format!("{} → {}", card.word, form.label()),
+                translation: conjugate(&card.word, class, *form)?,
+                category: card.category,
+                reading: None,
+                lure: lure(&card.word, class, *form),
+                audio: None,
+            })
+        })
+        .collect()
+}
+
+/// The per-kana sample clips, indexed by the characters of the hiragana decks.
+pub fn parse_kana_clips(
+    read: impl Fn(&str) -> Result<String, String>,
+) -> Result<KanaClips, String> {
+    let mut kana = Vec::new();
+    for file_name in [
+        "hiragana.deck.toml",
+        "hiragana_dakuten.deck.toml",
+        "hiragana_handakuken.deck.toml",
+    ] {
+        kana.extend(
+            parse_file(&read, file_name, parse_hiragana)?
+                .into_iter()
+                .map(|hiragana| (hiragana.character, hiragana.romaji)),
+        );
+    }
+    Ok(KanaClips::new(kana))
+}
+
+/// Reads `file_name` through `read` and parses it with `parse`.
+fn parse_file<T>(
+    read: &impl Fn(&str) -> Result<String, String>,
+    file_name: &str,
+    parse: impl FnOnce(&str) -> Result<T, toml::de::Error>,
+) -> Result<T, String> {
+    let content = read(file_name)?;
+    parse(&content).map_err(|err| format!("could not parse {}: {}", file_name, err))
+}
+
+fn parse_jlpt_words(content: &str) -> Result<Vec<JlptWord>, toml::de::Error> {
+    #[derive(Debug, Deserialize)]
+    struct JlptTable {
+        words: Vec<JlptWord>,
+    }
+
+    let temp_table: JlptTable = toml::from_str(content)?;
+    Ok(temp_table.words)
+}
+
+fn parse_translations(content: &str) -> Result<Vocabulary, toml::de::Error> {
+    toml::from_str(content)
+}
+
+fn parse_hiragana(content: &str) -> Result<Vec<Hiragana>, toml::de::Error> {
+    #[allow(dead_code)]
+    #[derive(Debug, Deserialize)]
+    struct HiraganaList {
+        hiragana: Vec<Hiragana>,
+    }
+
+    let temp_table: HiraganaList = toml::from_str(content)?;
+    Ok(temp_table.hiragana)
+}
@@ -0,0 +1,139 @@
+use crate::conjugation::{is_kana, to_hiragana};
+
+/// A run of a word's text, with its furigana when the run is written in kanji.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RubySegment {
+    pub base: String,
+    pub ruby: Option<String>,
+}
+
+/// Splits `word` into segments so that only its kanji carry furigana, e.g.
+/// 浴びる read あびる becomes 浴(あ) びる. When the kana in `word` can not be
+/// lined up with `reading`, the whole reading is put over the whole word.
+pub fn ruby_segments(word: &str, reading: &str) -> Vec<RubySegment> {
+    let runs = split_runs(word);
+    if reading.is_empty() || runs.iter().all(|(_, kana)| *kana) {
+        return vec![RubySegment {
+            base: word.to_owned(),
+            ruby: None,
+        }];
+    }
+
+    let reading: Vec<char> = reading.chars().collect();
+    match fit(&runs, &reading) {
+        Some(rubies) => {
+            let mut rubies = rubies.into_iter();
+            runs.into_iter()
+                .map(|(base, kana)| RubySegment {
+                    ruby: if kana { None } else { rubies.next() },
+                    base,
+                })
+                .collect()
+        }
+        None => vec![RubySegment {
+            base: word.to_owned(),
+            ruby: Some(reading.into_iter().collect()),
+        }],
+    }
+}
+
+/// Finds the reading of every kanji run, matching kana runs literally.
+fn fit(runs: &[(String, bool)], reading: &[char]) -> Option<Vec<String>> {
+    let Some(((text, kana), rest)) = runs.split_first() else {
+        return reading.is_empty().then(Vec::new);
+    };
+
+    if *kana {
+        let text: Vec<char> = text.chars().map(to_hiragana).collect();
+        let matches = reading.len() >= text.len()
+            && reading[..text.len()]
+                .iter()
+                .zip(&text)
+                .all(|(a, b)| to_hiragana(*a) == *b);
+        return if matches {
+            fit(rest, &reading[text.len()..])
+        } else {
+            None
+        };
+    }
+
+    (1..=reading.len()).find_map(|length| {
+        let mut rubies = fit(rest, &reading[length..])?;
+        rubies.insert(0, reading[..length].iter().collect());
+        Some(rubies)
+    })
+}
+
+/// Groups `word` into alternating runs of kana and non-kana characters.
+fn split_runs(word: &str) -> Vec<(String, bool)> {
+    let mut runs: Vec<(String, bool)> = Vec::new();
+    for c in word.chars() {
+        let kana = is_kana(c);
+        match runs.last_mut() {
+            Some((text, run_kana)) if *run_kana == kana => text.push(c),
+            _ => runs.push((c.to_string(), kana)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(word: &str, reading: &str) -> Vec<(String, Option<String>)> {
+        ruby_segments(word, reading)
+            .into_iter()
+            .map(|segment| (segment.base, segment.ruby))
+            .collect()
+    }
+
+    fn kana(base: &str) -> (String, Option<String>) {
+        (base.to_owned(), None)
+    }
+
+    fn kanji(base: &str, ruby: &str) -> (String, Option<String>) {
+        (base.to_owned(), Some(ruby.to_owned()))
+    }
+
+    #[test]
+    fn only_kanji_runs_get_furigana() {
+        assert_eq!(
+            segments("浴びる", "あびる"),
+            [kanji("浴", "あ"), kana("びる")]
+        );
+        assert_eq!(
+            segments("お茶", "おちゃ"),
+            [kana("お"), kanji("茶", "ちゃ")]
+        );
+        assert_eq!(
+            segments("持ち帰る", "もちかえる"),
+            [
+                kanji("持", "も"),
+                kana("ち"),
+                kanji("帰", "かえ"),
+                kana("る")
+            ]
+        );
+        assert_eq!(segments("日本", "にほん"), [kanji("日本", "にほん")]);
+    }
+
+    #[test]
+    fn katakana_lines_up_with_a_hiragana_reading() {
+        assert_eq!(
+            segments("ガス台", "がすだい"),
+            [kana("ガス"), kanji("台", "だい")]
+        );
+    }
+
+    #[test]
+    fn kana_words_and_empty_readings_get_none() {
+        assert_eq!(segments("ひらがな", "ひらがな"), [kana("ひらがな")]);
+        assert_eq!(segments("山", ""), [kana("山")]);
+    }
+
+    #[test]
+    fn a_reading_that_does_not_line_up_goes_over_the_whole_word() {
+        assert_eq!(segments("浴びる", "あぶる"), [kanji("浴びる", "あぶる")]);
+    }
+}
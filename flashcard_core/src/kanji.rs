@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::JlptLevel;
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct Kanji {
+    pub character: String,
+    /// On'yomi in katakana.
+    pub onyomi: Vec<String>,
+    /// Kun'yomi in hiragana, with a `.` before the okurigana (た.べる).
+    pub kunyomi: Vec<String>,
+    pub meanings: Vec<String>,
+    pub strokes: u32,
+    pub jlpt_level: JlptLevel,
+}
+
+impl Kanji {
+    pub fn meaning(&self) -> String {
+        self.meanings.join(", ")
+    }
+
+    /// On'yomi and kun'yomi separated by a slash, e.g. "ショク / た.べる".
+    pub fn readings(&self) -> String {
+        [self.onyomi.join(", "), self.kunyomi.join(", ")]
+            .into_iter()
+            .filter(|readings| !readings.is_empty())
+            .collect::<Vec<_>>()
+            .join(" / ")
+    }
+}
+
+/// Every known kanji by character, used to break vocabulary down into the
+/// kanji it is written with.
+#[derive(Debug, Default)]
+pub struct KanjiIndex {
+    kanji: HashMap<char, Kanji>,
+}
+
+impl KanjiIndex {
+    pub fn new(kanji_list: Vec<Kanji>) -> Self {
+        KanjiIndex {
+            kanji: kanji_list
+                .into_iter()
+                .filter_map(|kanji| Some((kanji.character.chars().next()?, kanji)))
+                .collect(),
+        }
+    }
+
+    /// The known kanji contained in `word`, in the order they are written.
+    pub fn kanji_in(&self, word: &str) -> Vec<&Kanji> {
+        let mut found: Vec<&Kanji> = Vec::new();
+        for kanji in word.chars().filter_map(|c| self.kanji.get(&c)) {
+            if !found.iter().any(|k| k.character == kanji.character) {
+                found.push(kanji);
+            }
+        }
+        found
+    }
+
+    /// One line per kanji in `word`, e.g. "食 eat, food (ショク / た.べる) 9 strokes".
+    pub fn describe(&self, word: &str) -> String {
+        self.kanji_in(word)
+            .iter()
+            .map(|kanji| {
+                format!(
+                    "\n{} {} ({}) {} strokes",
+                    kanji.character,
+                    kanji.meaning(),
+                    kanji.readings(),
+                    kanji.strokes
+                )
+            })
+            .collect()
+    }
+}
+
+pub fn read_kanji_file(file_name: &str) -> Result<Vec<Kanji>, String> {
+    let content = fs::read_to_string(file_name)
+        .map_err(|err| format!("could not read {}: {}", file_name, err))?;
+    parse_kanji(&content).map_err(|err| format!("could not parse {}: {}", file_name, err))
+}
+
+/// Parses the contents of a kanji file.
+pub fn parse_kanji(content: &str) -> Result<Vec<Kanji>, toml::de::Error> {
+    #[derive(Debug, Deserialize)]
+    struct KanjiList {
+        kanji: Vec<Kanji>,
+    }
+    let temp_table: KanjiList = toml::from_str(content)?;
+    Ok(temp_table.kanji)
+}
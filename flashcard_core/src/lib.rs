@@ -0,0 +1,30 @@
+//! Quiz logic shared by every frontend: decks, card selection, answer checking
+//! and review weights. Nothing in here depends on the game engine.
+//!
+//! ```no_run
+//! use flashcard_core::*;
+//!
+//! let cards = load_cards(&[DeckChoice::Hiragana], &JlptLevel::ALL, &[], SessionMode::Recall)?;
+//! let mut quiz = Quiz::try_new(cards)?;
+//! let prompt = quiz.next_prompt(&mut rand::thread_rng());
+//! let outcome = quiz.submit(&prompt, prompt.correct_index);
+//! assert!(outcome.correct);
+//! # Ok::<(), String>(())
+//! ```
+
+pub mod conjugation;
+pub mod furigana;
+pub mod kanji;
+pub mod pronunciation;
+
+mod deck;
+pub use deck::*;
+
+mod progress;
+pub use progress::*;
+
+mod quiz;
+pub use quiz::*;
+
+mod study;
+pub use study::*;
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+use crate::Quiz;
+
+/// Where every frontend keeps its progress, so reviews done in one count in the others.
+pub const PROGRESS_FILE: &str = "progress.toml";
+
+/// The weight and review count of every card ever answered, matched to cards
+/// by their word and translation.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Progress {
+    #[serde(default)]
+    cards: Vec<CardProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CardProgress {
+    word: String,
+    translation: String,
+    weight: f32,
+    reviews: u32,
+}
+
+impl Progress {
+    /// Reads a progress file, a missing file is no progress yet.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                toml::from_str(&content).map_err(|err| format!("could not parse {}: {}", path, err))
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Progress::default()),
+            Err(err) => Err(format!("could not read {}: {}", path, err)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, content).map_err(|err| format!("could not write {}: {}", path, err))
+    }
+}
+
+impl Quiz {
+    /// Picks up the weights and review counts saved for this quiz's cards.
+    pub fn restore(&mut self, progress: &Progress) {
+        let saved: HashMap<(&str, &str), &CardProgress> = progress
+            .cards
+            .iter()
+            .map(|card| ((card.word.as_str(), card.translation.as_str()), card))
+            .collect();
+        for (index, card) in self.cards.iter().enumerate() {
+            if let Some(saved) = saved.get(&(card.word.as_str(), card.translation.as_str())) {
+                self.weights[index] = saved.weight;
+                self.reviews[index] = saved.reviews;
+            }
+        }
+    }
+
+    /// Writes the state of this quiz's answered cards into `progress`, keeping
+    /// the cards of other decks as they are.
+    pub fn record(&self, progress: &mut Progress) {
+        let mut positions: HashMap<(String, String), usize> = progress
+            .cards
+            .iter()
+            .enumerate()
+            .map(|(position, card)| ((card.word.clone(), card.translation.clone()), position))
+            .collect();
+        for (index, card) in self.cards.iter().enumerate() {
+            if self.reviews[index] == 0 {
+                continue;
+            }
+            let entry = CardProgress {
+                word: card.word.clone(),
+                translation: card.translation.clone(),
+                weight: self.weights[index],
+                reviews: self.reviews[index],
+            };
+            match positions.get(&(card.word.clone(), card.translation.clone())) {
+                Some(position) => progress.cards[*position] = entry,
+                None => {
+                    positions.insert(
+                        (card.word.clone(), card.translation.clone()),
+                        progress.cards.len(),
+                    );
+                    progress.cards.push(entry);
+                }
+            }
+        }
+    }
+
+    /// Applies the answers given since the last call on top of the cards in
+    /// `progress`, whatever weights this quiz started from, so reviews saved
+    /// meanwhile by another run or frontend are kept.
+    pub fn record_answers(&mut self, progress: &mut Progress) {
+        let rules = self.rules;
+        for (word, translation, correct) in self.unsaved.drain(..) {
+            let position = match progress
+                .cards
+                .iter()
+                .position(|card| card.word == word && card.translation == translation)
+            {
+                Some(position) => position,
+                None => {
+                    progress.cards.push(CardProgress {
+                        word,
+                        translation,
+                        weight: 1.,
+                        reviews: 0,
+                    });
+                    progress.cards.len() - 1
+                }
+            };
+            let card = &mut progress.cards[position];
+            card.weight = rules.apply(card.weight, correct);
+            card.reviews += 1;
+        }
+    }
+
+    /// Adds the answers given since the last save to the progress file at `path`.
+    pub fn save_progress(&mut self, path: &str) -> Result<(), String> {
+        if self.unsaved.is_empty() {
+            return Ok(());
+        }
+        let mut progress = Progress::load(path)?;
+        self.record_answers(&mut progress);
+        progress.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Card, CardId, Prompt};
+
+    fn quiz(words: &[(&str, &str)]) -> Quiz {
+        Quiz::new(
+            words
+                .iter()
+                .map(|(word, translation)| Card {
+                    word: word.to_string(),
+                    translation: translation.to_string(),
+                    category: None,
+                    reading: None,
+                    lure: None,
+                    audio: None,
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn answered_cards_are_restored_by_word_and_translation() {
+        let mut studied = quiz(&[("あ", "a"), ("い", "i"), ("う", "u")]);
+        studied.weights[1] = 51.;
+        studied.reviews[1] = 1;
+        let mut progress = Progress::default();
+        studied.record(&mut progress);
+        // unanswered cards are left out
+        assert_eq!(progress.cards.len(), 1);
+
+        let mut next = quiz(&[("い", "i"), ("え", "e")]);
+        next.restore(&progress);
+        assert_eq!(next.weights, [51., 1.]);
+        assert_eq!(next.reviews, [1, 0]);
+    }
+
+    #[test]
+    fn recording_keeps_the_cards_of_other_decks() {
+        let mut hiragana = quiz(&[("あ", "a"), ("い", "i")]);
+        hiragana.reviews[0] = 1;
+        let mut progress = Progress::default();
+        hiragana.record(&mut progress);
+
+        let mut kanji = quiz(&[("山", "mountain"), ("川", "river")]);
+        kanji.reviews[1] = 2;
+        kanji.weights[1] = 101.;
+        kanji.record(&mut progress);
+
+        hiragana.weights[0] = 3.;
+        hiragana.reviews[0] = 4;
+        hiragana.record(&mut progress);
+
+        let mut both = quiz(&[("あ", "a"), ("川", "river")]);
+        both.restore(&progress);
+        assert_eq!(progress.cards.len(), 2);
+        assert_eq!(both.weights, [3., 101.]);
+        assert_eq!(both.reviews, [4, 2]);
+    }
+
+    fn answer(quiz: &mut Quiz, card: usize, correct: bool) {
+        let prompt = Prompt {
+            card: CardId(card),
+            choices: vec![String::from("right"), String::from("wrong")],
+            correct_index: 0,
+        };
+        quiz.submit(&prompt, if correct { 0 } else { 1 });
+    }
+
+    #[test]
+    fn progress_survives_a_save_and_load() {
+        let mut studied = quiz(&[("あ", "a"), ("い", "i")]);
+        answer(&mut studied, 0, false);
+        answer(&mut studied, 0, true);
+        let path = std::env::temp_dir().join(format!("progress-{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        studied.save_progress(path).unwrap();
+        // saved answers are not added again
+        studied.save_progress(path).unwrap();
+
+        let mut loaded = quiz(&[("あ", "a"), ("い", "i")]);
+        loaded.restore(&Progress::load(path).unwrap());
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.weights, [25.5, 1.]);
+        assert_eq!(loaded.reviews, [2, 0]);
+    }
+
+    #[test]
+    fn answers_are_saved_on_top_of_newer_progress() {
+        // a run drawing from an old snapshot, while the file moved on
+        let mut snapshot = quiz(&[("あ", "a"), ("い", "i")]);
+        snapshot.weights[1] = 51.;
+        snapshot.reviews[1] = 1;
+        let mut progress = Progress::default();
+        let mut newer = quiz(&[("あ", "a"), ("い", "i")]);
+        newer.weights = vec![26., 4.];
+        newer.reviews = vec![3, 5];
+        newer.record(&mut progress);
+
+        answer(&mut snapshot, 0, false);
+        snapshot.record_answers(&mut progress);
+
+        let mut loaded = quiz(&[("あ", "a"), ("い", "i")]);
+        loaded.restore(&progress);
+        assert_eq!(loaded.weights, [76., 4.]);
+        assert_eq!(loaded.reviews, [4, 5]);
+    }
+}
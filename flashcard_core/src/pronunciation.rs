@@ -0,0 +1,146 @@
+//! Which sound clips pronounce a card: its own recording when the deck has
+//! one, or else its kana read out one sample clip at a time.
+
+use std::collections::HashMap;
+
+use crate::conjugation::to_hiragana;
+use crate::Card;
+
+/// Folder of the per-kana clips, one `<romaji>.ogg` for every kana of the
+/// hiragana decks, e.g. `audio/kana/ka.ogg` for か, one for every combination
+/// with a small ゃゅょ, e.g. `kyo.ogg` for きょ, and `pause.ogg` for っ.
+pub const KANA_CLIP_DIR: &str = "audio/kana";
+
+/// The clip played for っ, a short silence before the doubled consonant.
+const PAUSE: &str = "pause";
+
+/// Sample clip of every kana, built from the hiragana decks.
+#[derive(Debug, Clone, Default)]
+pub struct KanaClips(HashMap<char, String>);
+
+impl KanaClips {
+    /// Indexes kana by their `(character, romaji)` pairs. Entries that are
+    /// not a single character are left out.
+    pub fn new(kana: impl IntoIterator<Item = (String, String)>) -> Self {
+        KanaClips(
+            kana.into_iter()
+                .filter_map(|(character, romaji)| {
+                    let mut chars = character.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Some((c, romaji)),
+                        _ => None,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The clips reading out `text` kana by kana, katakana read as the same
+    /// hiragana. A small ゃゅょ merges with the kana before it, っ is a pause
+    /// and ー repeats the vowel before it. `None` if a character has no clip,
+    /// since a word with sounds left out would be pronounced wrong.
+    pub fn clips(&self, text: &str) -> Option<Vec<String>> {
+        let mut sounds: Vec<String> = Vec::new();
+        for c in text.chars().map(to_hiragana) {
+            let sound = match c {
+                'ゃ' | 'ゅ' | 'ょ' => {
+                    let vowel = match c {
+                        'ゃ' => 'a',
+                        'ゅ' => 'u',
+                        _ => 'o',
+                    };
+                    // き and ょ are kyo, while し, ち and じ drop the y: sho, cho, jo
+                    let stem = sounds.pop()?.strip_suffix('i')?.to_owned();
+                    if stem.is_empty() {
+                        return None;
+                    }
+                    if stem.ends_with('h') || stem == "j" {
+                        format!("{}{}", stem, vowel)
+                    } else {
+                        format!("{}y{}", stem, vowel)
+                    }
+                }
+                'っ' => String::from(PAUSE),
+                'ー' => sounds
+                    .last()
+                    .filter(|sound| *sound != PAUSE)
+                    .and_then(|sound| sound.chars().last())
+                    .filter(|last| "aiueo".contains(*last))?
+                    .to_string(),
+                _ => self.0.get(&c)?.clone(),
+            };
+            sounds.push(sound);
+        }
+        Some(
+            sounds
+                .iter()
+                .map(|sound| format!("{}/{}.ogg", KANA_CLIP_DIR, sound))
+                .collect(),
+        )
+    }
+
+    /// The clips pronouncing `card`: its `audio` if it has one, otherwise its
+    /// reading, or its word when it is written in kana only.
+    pub fn pronounce(&self, card: &Card) -> Option<Vec<String>> {
+        if let Some(audio) = &card.audio {
+            return Some(vec![audio.clone()]);
+        }
+        self.clips(card.reading.as_deref().unwrap_or(&card.word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kana() -> KanaClips {
+        KanaClips::new(
+            [
+                ("き", "ki"),
+                ("う", "u"),
+                ("が", "ga"),
+                ("こ", "ko"),
+                ("ひ", "hi"),
+                ("し", "shi"),
+                ("ゆ", "yu"),
+                ("ん", "n"),
+            ]
+            .map(|(character, romaji)| (character.to_owned(), romaji.to_owned())),
+        )
+    }
+
+    fn sounds(text: &str) -> Option<Vec<String>> {
+        kana().clips(text).map(|clips| {
+            clips
+                .iter()
+                .map(|clip| {
+                    clip.trim_start_matches("audio/kana/")
+                        .trim_end_matches(".ogg")
+                        .to_owned()
+                })
+                .collect()
+        })
+    }
+
+    #[test]
+    fn small_kana_merge_with_the_kana_before() {
+        assert_eq!(sounds("きょう").unwrap(), ["kyo", "u"]);
+        assert_eq!(sounds("しゅう").unwrap(), ["shu", "u"]);
+        // nothing before to merge with, or nothing ending in i
+        assert_eq!(sounds("ょう"), None);
+        assert_eq!(sounds("ゆゅ"), None);
+    }
+
+    #[test]
+    fn small_tsu_pauses_and_the_long_vowel_mark_repeats_the_vowel() {
+        assert_eq!(sounds("がっこう").unwrap(), ["ga", "pause", "ko", "u"]);
+        assert_eq!(sounds("コーヒー").unwrap(), ["ko", "o", "hi", "i"]);
+        assert_eq!(sounds("ンー"), None);
+        assert_eq!(sounds("ー"), None);
+    }
+
+    #[test]
+    fn kana_without_a_clip_leave_the_word_silent() {
+        assert_eq!(sounds("きた"), None);
+    }
+}
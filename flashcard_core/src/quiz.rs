@@ -0,0 +1,408 @@
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::HashMap;
+
+use crate::Category;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub word: String,
+    pub translation: String,
+    pub category: Option<Category>,
+    /// Kana reading of `word`, when the deck has one.
+    pub reading: Option<String>,
+    /// Wrong answer made for this card, offered instead of another card's translation.
+    pub lure: Option<String>,
+    /// Pronunciation clip under the asset root, when the deck has one.
+    pub audio: Option<String>,
+}
+
+/// A card in a `Quiz`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CardId(pub(crate) usize);
+
+/// One question: the card asked and the answers to choose from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prompt {
+    pub card: CardId,
+    pub choices: Vec<String>,
+    pub correct_index: usize,
+}
+
+/// The result of answering a prompt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Outcome {
+    pub card: CardId,
+    pub correct: bool,
+    /// Right answers in a row, including this one.
+    pub streak: u32,
+}
+
+/// When the prompt shows furigana over kanji.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuriganaMode {
+    Always,
+    Never,
+    /// Only for new cards and cards whose weight is still raised by misses,
+    /// until enough right answers bring it back down.
+    Auto,
+}
+
+impl std::str::FromStr for FuriganaMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "always" => Ok(FuriganaMode::Always),
+            "never" => Ok(FuriganaMode::Never),
+            "auto" => Ok(FuriganaMode::Auto),
+            _ => Err(format!(
+                "unknown furigana mode \"{}\" (expected always, never or auto)",
+                s
+            )),
+        }
+    }
+}
+
+/// How answers change a card's weight.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReviewRules {
+    /// Added to the weight of a missed card.
+    pub miss_penalty: f32,
+    /// Multiplies the weight of a card answered right, down to 1.
+    pub hit_factor: f32,
+}
+
+impl ReviewRules {
+    /// The weight of a card after answering it right or wrong.
+    pub fn apply(&self, weight: f32, correct: bool) -> f32 {
+        if correct {
+            (weight * self.hit_factor).max(1.)
+        } else {
+            weight + self.miss_penalty
+        }
+    }
+}
+
+impl Default for ReviewRules {
+    fn default() -> Self {
+        ReviewRules {
+            miss_penalty: 50.,
+            hit_factor: 0.5,
+        }
+    }
+}
+
+/// The cards being studied and how well each one is known. Cards are drawn
+/// by weight, so missed cards come back more often than known ones.
+#[derive(Debug)]
+pub struct Quiz {
+    pub(crate) cards: Vec<Card>,
+    pub(crate) weights: Vec<f32>,
+    /// How many times each card has been answered.
+    pub(crate) reviews: Vec<u32>,
+    /// Word, translation and result of the answers not yet saved to progress.
+    pub(crate) unsaved: Vec<(String, String, bool)>,
+    streak: u32,
+    pub(crate) rules: ReviewRules,
+}
+
+/// Every card needs a wrong answer, so there must be two different translations.
+fn check_cards(cards: &[Card]) -> Result<(), String> {
+    match cards.first() {
+        Some(first)
+            if cards
+                .iter()
+                .any(|card| card.translation != first.translation) =>
+        {
+            Ok(())
+        }
+        _ => Err(String::from(
+            "the selected decks and filters leave fewer than two different answers to study",
+        )),
+    }
+}
+
+impl Quiz {
+    pub fn new(cards: Vec<Card>) -> Self {
+        Quiz::try_new(cards).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Like `new`, failing instead of panicking when there are not enough cards.
+    pub fn try_new(cards: Vec<Card>) -> Result<Self, String> {
+        check_cards(&cards)?;
+        Ok(Quiz {
+            weights: vec![1.0; cards.len()],
+            reviews: vec![0; cards.len()],
+            unsaved: Vec::new(),
+            cards,
+            streak: 0,
+            rules: ReviewRules::default(),
+        })
+    }
+
+    /// Swaps in a new set of cards, like a deck edited while studying. Cards
+    /// with the same word and translation as before keep their weight and
+    /// reviews. Ids of the old cards no longer apply.
+    pub fn replace_cards(&mut self, cards: Vec<Card>) -> Result<(), String> {
+        check_cards(&cards)?;
+        let known: HashMap<(&str, &str), (f32, u32)> = self
+            .cards
+            .iter()
+            .zip(self.weights.iter().zip(&self.reviews))
+            .map(|(card, (weight, reviews))| {
+                (
+                    (card.word.as_str(), card.translation.as_str()),
+                    (*weight, *reviews),
+                )
+            })
+            .collect();
+        let (weights, reviews) = cards
+            .iter()
+            .map(|card| {
+                known
+                    .get(&(card.word.as_str(), card.translation.as_str()))
+                    .copied()
+                    .unwrap_or((1.0, 0))
+            })
+            .unzip();
+
+        self.weights = weights;
+        self.reviews = reviews;
+        self.cards = cards;
+        Ok(())
+    }
+
+    pub fn card(&self, id: CardId) -> &Card {
+        &self.cards[id.0]
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// How likely `id` is to be drawn next, relative to the other cards.
+    pub fn weight(&self, id: CardId) -> f32 {
+        self.weights[id.0]
+    }
+
+    pub fn streak(&self) -> u32 {
+        self.streak
+    }
+
+    pub fn set_rules(&mut self, rules: ReviewRules) {
+        self.rules = rules;
+    }
+
+    /// Draws a card by weight and puts its translation among the choices, next
+    /// to its lure or the translation of another card.
+    pub fn next_prompt(&self, rng: &mut impl Rng) -> Prompt {
+        let distribution = WeightedIndex::new(&self.weights).unwrap();
+        let index = distribution.sample(rng);
+        let card = &self.cards[index];
+
+        let wrong = match &card.lure {
+            Some(lure) => lure.clone(),
+            None => {
+                let others: Vec<&Card> = self
+                    .cards
+                    .iter()
+                    .filter(|other| other.translation != card.translation)
+                    .collect();
+                // `check_cards` makes sure there is another translation
+                others
+                    .choose(rng)
+                    .expect("no card with another translation")
+                    .translation
+                    .clone()
+            }
+        };
+
+        let correct_index = rng.gen_range(0..2);
+        let mut choices = vec![wrong];
+        choices.insert(correct_index, card.translation.clone());
+
+        Prompt {
+            card: CardId(index),
+            choices,
+            correct_index,
+        }
+    }
+
+    /// The reading to show over the prompt of `id`, if any.
+    pub fn furigana(&self, id: CardId, mode: FuriganaMode) -> Option<&str> {
+        let shown = match mode {
+            FuriganaMode::Always => true,
+            FuriganaMode::Never => false,
+            // new cards and cards still weighted up by misses, which takes a few
+            // right answers to wear off
+            FuriganaMode::Auto => self.reviews[id.0] == 0 || self.weights[id.0] > 1.,
+        };
+        self.card(id).reading.as_deref().filter(|_| shown)
+    }
+
+    /// Answers `prompt` with the choice at `choice`. By default a right answer
+    /// halves the card's weight down to 1, a miss adds 50 so the card comes back soon.
+    pub fn submit(&mut self, prompt: &Prompt, choice: usize) -> Outcome {
+        let index = prompt.card.0;
+        let correct = choice == prompt.correct_index;
+
+        self.reviews[index] += 1;
+        self.weights[index] = self.rules.apply(self.weights[index], correct);
+        if correct {
+            self.streak += 1;
+        } else {
+            self.streak = 0;
+        }
+        let card = &self.cards[index];
+        self.unsaved
+            .push((card.word.clone(), card.translation.clone(), correct));
+
+        Outcome {
+            card: prompt.card,
+            correct,
+            streak: self.streak,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn card(word: &str, translation: &str) -> Card {
+        Card {
+            word: word.to_owned(),
+            translation: translation.to_owned(),
+            category: None,
+            reading: None,
+            lure: None,
+            audio: None,
+        }
+    }
+
+    fn hiragana() -> Vec<Card> {
+        vec![card("あ", "a"), card("い", "i"), card("う", "u")]
+    }
+
+    /// A prompt for `index` with its answer first.
+    fn prompt(index: usize) -> Prompt {
+        Prompt {
+            card: CardId(index),
+            choices: vec![String::new(), String::new()],
+            correct_index: 0,
+        }
+    }
+
+    #[test]
+    fn hits_halve_the_weight_down_to_one_and_misses_add_to_it() {
+        let mut quiz = Quiz::new(hiragana());
+        let outcome = quiz.submit(&prompt(0), 1);
+        assert!(!outcome.correct);
+        assert_eq!(quiz.weight(CardId(0)), 51.);
+
+        assert!(quiz.submit(&prompt(0), 0).correct);
+        assert_eq!(quiz.weight(CardId(0)), 25.5);
+        for _ in 0..10 {
+            quiz.submit(&prompt(0), 0);
+        }
+        assert_eq!(quiz.weight(CardId(0)), 1.);
+        assert_eq!(quiz.streak(), 11);
+    }
+
+    #[test]
+    fn rules_change_the_weighting() {
+        let mut quiz = Quiz::new(hiragana());
+        quiz.set_rules(ReviewRules {
+            miss_penalty: 10.,
+            hit_factor: 0.9,
+        });
+        quiz.submit(&prompt(1), 1);
+        assert_eq!(quiz.weight(CardId(1)), 11.);
+        quiz.submit(&prompt(1), 0);
+        assert_eq!(quiz.weight(CardId(1)), 9.9);
+    }
+
+    #[test]
+    fn wrong_choice_is_another_translation() {
+        let quiz = Quiz::new(vec![
+            card("かみ", "paper"),
+            card("紙", "paper"),
+            card("神", "god"),
+        ]);
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            let prompt = quiz.next_prompt(&mut rng);
+            let answer = &quiz.card(prompt.card).translation;
+            assert_eq!(&prompt.choices[prompt.correct_index], answer);
+            assert_ne!(&prompt.choices[1 - prompt.correct_index], answer);
+        }
+    }
+
+    #[test]
+    fn lure_is_offered_instead_of_another_translation() {
+        let mut cards = hiragana();
+        cards[0].lure = Some(String::from("o"));
+        cards[1].lure = Some(String::from("e"));
+        cards[2].lure = Some(String::from("a"));
+        let quiz = Quiz::new(cards);
+        let prompt = quiz.next_prompt(&mut StdRng::seed_from_u64(2));
+        let lure = quiz.card(prompt.card).lure.as_ref().unwrap();
+        assert_eq!(&prompt.choices[1 - prompt.correct_index], lure);
+    }
+
+    #[test]
+    fn cards_sharing_one_translation_are_not_enough() {
+        assert!(Quiz::try_new(vec![card("あ", "a"), card("ア", "a")]).is_err());
+        assert!(Quiz::try_new(vec![card("あ", "a")]).is_err());
+
+        let mut quiz = Quiz::new(hiragana());
+        assert!(quiz
+            .replace_cards(vec![card("あ", "a"), card("あ", "a")])
+            .is_err());
+        assert_eq!(quiz.cards().len(), 3);
+    }
+
+    #[test]
+    fn replaced_cards_keep_their_weights() {
+        let mut quiz = Quiz::new(hiragana());
+        quiz.submit(&prompt(1), 1);
+        quiz.replace_cards(vec![card("え", "e"), card("い", "i"), card("い", "yi")])
+            .unwrap();
+        assert_eq!(quiz.weight(CardId(0)), 1.);
+        assert_eq!(quiz.weight(CardId(1)), 51.);
+        assert_eq!(quiz.weight(CardId(2)), 1.);
+    }
+
+    #[test]
+    fn furigana_modes() {
+        let mut cards = hiragana();
+        cards[0] = Card {
+            reading: Some(String::from("かみ")),
+            ..card("紙", "paper")
+        };
+        let mut quiz = Quiz::new(cards);
+        let paper = CardId(0);
+
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Always), Some("かみ"));
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Never), None);
+        assert_eq!(quiz.furigana(CardId(1), FuriganaMode::Always), None);
+
+        // new cards show it, known ones do not
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Auto), Some("かみ"));
+        quiz.submit(&prompt(0), 0);
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Auto), None);
+        quiz.submit(&prompt(0), 1);
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Auto), Some("かみ"));
+        // one right answer halves the miss, it stays until the weight is back to 1
+        quiz.submit(&prompt(0), 0);
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Auto), Some("かみ"));
+        while quiz.weight(paper) > 1. {
+            quiz.submit(&prompt(0), 0);
+        }
+        assert_eq!(quiz.furigana(paper, FuriganaMode::Auto), None);
+    }
+}
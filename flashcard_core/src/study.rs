@@ -0,0 +1,118 @@
+use crate::{parse_levels, Category, DeckChoice, FuriganaMode, JlptLevel, SessionMode};
+
+/// What to study, picked with the same flags in every frontend:
+/// `--deck`, `--jlpt`, `--category`, `--mode` and `--furigana`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StudyOptions {
+    pub decks: Vec<DeckChoice>,
+    /// Levels kept from the JLPT and kanji decks, other decks are not filtered by level.
+    pub jlpt_levels: Vec<JlptLevel>,
+    /// Only cards tagged with one of these categories are studied, empty keeps every card.
+    pub categories: Vec<Category>,
+    pub mode: SessionMode,
+    pub furigana: FuriganaMode,
+}
+
+impl Default for StudyOptions {
+    fn default() -> Self {
+        StudyOptions {
+            decks: SessionMode::Recall.default_decks(),
+            jlpt_levels: JlptLevel::ALL.to_vec(),
+            categories: Vec::new(),
+            mode: SessionMode::Recall,
+            furigana: FuriganaMode::Auto,
+        }
+    }
+}
+
+impl StudyOptions {
+    /// Parses the study flags in `args`. Any other flag is handed to `other`
+    /// with a way to read its value, which returns `false` for a flag the
+    /// frontend does not know either. Without `--deck`, the mode picks the decks.
+    pub fn from_args<F>(
+        mut args: impl Iterator<Item = String>,
+        mut other: F,
+    ) -> Result<Self, String>
+    where
+        F: FnMut(&str, &mut dyn FnMut() -> Result<String, String>) -> Result<bool, String>,
+    {
+        let mut options = StudyOptions::default();
+        let mut decks = None;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for {}", arg))
+            };
+            match arg.as_str() {
+                "--deck" => {
+                    decks = Some(
+                        value()?
+                            .split(',')
+                            .map(str::parse)
+                            .collect::<Result<_, _>>()?,
+                    )
+                }
+                "--jlpt" => options.jlpt_levels = parse_levels(&value()?)?,
+                "--category" => {
+                    options.categories = value()?
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<Result<_, _>>()?;
+                }
+                "--mode" => options.mode = value()?.parse()?,
+                "--furigana" => options.furigana = value()?.parse()?,
+                _ => {
+                    if !other(&arg, &mut value)? {
+                        return Err(format!("unknown argument \"{}\"", arg));
+                    }
+                }
+            }
+        }
+
+        options.decks = decks.unwrap_or_else(|| options.mode.default_decks());
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> impl Iterator<Item = String> + '_ {
+        line.split_whitespace().map(str::to_owned)
+    }
+
+    #[test]
+    fn the_mode_picks_the_decks_unless_they_are_given() {
+        let options =
+            StudyOptions::from_args(args("--mode conjugation"), |_, _| Ok(false)).unwrap();
+        assert_eq!(options.decks, [DeckChoice::Vocabulary]);
+
+        let options = StudyOptions::from_args(
+            args("--deck hiragana --mode conjugation --jlpt N5"),
+            |_, _| Ok(false),
+        )
+        .unwrap();
+        assert_eq!(options.decks, [DeckChoice::Hiragana]);
+        assert_eq!(options.jlpt_levels, [JlptLevel::N5]);
+    }
+
+    #[test]
+    fn other_flags_go_to_the_frontend() {
+        let mut seed = None;
+        let options = StudyOptions::from_args(args("--seed 7 --furigana never"), |arg, value| {
+            match arg {
+                "--seed" => seed = Some(value()?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(seed.as_deref(), Some("7"));
+        assert_eq!(options.furigana, FuriganaMode::Never);
+
+        assert!(StudyOptions::from_args(args("--seed"), |_, value| value().map(|_| true)).is_err());
+        assert!(StudyOptions::from_args(args("--speed 3"), |_, _| Ok(false)).is_err());
+    }
+}
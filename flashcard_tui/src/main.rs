@@ -0,0 +1,156 @@
+//! Drills the same decks as the game in a terminal, sharing its progress file.
+//!
+//! ```text
+//! flashcard_tui --deck hiragana
+//! flashcard_tui --deck jlpt --jlpt N5 --furigana never
+//! flashcard_tui --mode conjugation
+//! ```
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Print, Stylize},
+    terminal::{self, ClearType},
+};
+use std::io::{self, Write};
+
+use flashcard_core::kanji::{read_kanji_file, KanjiIndex};
+use flashcard_core::*;
+
+/// Raw mode on the alternate screen, restored when dropped, also on a panic.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn main() {
+    fn exit_with(err: String) -> ! {
+        eprintln!("{}", err);
+        std::process::exit(2);
+    }
+
+    // the same study flags as the game, and none of its own
+    let options = StudyOptions::from_args(std::env::args().skip(1), |_, _| Ok(false))
+        .unwrap_or_else(|err| exit_with(err));
+
+    let mut quiz = load_cards(
+        &options.decks,
+        &options.jlpt_levels,
+        &options.categories,
+        options.mode,
+    )
+    .and_then(Quiz::try_new)
+    .unwrap_or_else(|err| exit_with(err));
+    match Progress::load(PROGRESS_FILE) {
+        Ok(progress) => quiz.restore(&progress),
+        Err(err) => eprintln!("starting without saved progress: {}", err),
+    }
+    let kanji_index = read_kanji_file(&format!("{}/kanji.deck.toml", DECK_DIR))
+        .map(KanjiIndex::new)
+        .unwrap_or_else(|err| exit_with(err));
+
+    if let Err(err) = run(&mut quiz, &kanji_index, options.furigana) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn run(quiz: &mut Quiz, kanji_index: &KanjiIndex, furigana: FuriganaMode) -> io::Result<()> {
+    let _terminal = RawTerminal::enter()?;
+    let mut rng = rand::thread_rng();
+    let mut feedback =
+        String::from("Left, 1 or A picks the first answer, Right, 2 or D the second. Q quits.");
+
+    loop {
+        let prompt = quiz.next_prompt(&mut rng);
+        draw(
+            quiz,
+            &prompt,
+            quiz.furigana(prompt.card, furigana),
+            &feedback,
+        )?;
+        let Some(choice) = read_choice()? else {
+            return Ok(());
+        };
+
+        let outcome = quiz.submit(&prompt, choice);
+        let card = quiz.card(outcome.card);
+        feedback = format!(
+            "{}: \"{}\" => \"{}\"{}",
+            if outcome.correct { "Yes" } else { "No" },
+            card.word,
+            card.translation,
+            kanji_index.describe(&card.word)
+        );
+        if let Err(err) = quiz.save_progress(PROGRESS_FILE) {
+            feedback.push('\n');
+            feedback.push_str(&err);
+        }
+    }
+}
+
+fn draw(quiz: &Quiz, prompt: &Prompt, furigana: Option<&str>, feedback: &str) -> io::Result<()> {
+    let card = quiz.card(prompt.card);
+    let mut out = io::stdout();
+
+    queue!(
+        out,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(2, 1),
+        Print(format!("streak {}", quiz.streak()).dim())
+    )?;
+    if let Some(category) = card.category {
+        queue!(out, cursor::MoveTo(4, 3), Print(category.label().dim()))?;
+    }
+    if let Some(reading) = furigana {
+        queue!(out, cursor::MoveTo(4, 4), Print(reading))?;
+    }
+    queue!(
+        out,
+        cursor::MoveTo(4, 5),
+        Print(card.word.as_str().bold()),
+        cursor::MoveTo(4, 7),
+        Print(format!("← {}", prompt.choices[0])),
+        cursor::MoveTo(4, 8),
+        Print(format!("→ {}", prompt.choices[1]))
+    )?;
+    for (row, line) in (11..).zip(feedback.lines()) {
+        queue!(out, cursor::MoveTo(2, row), Print(line))?;
+    }
+    out.flush()
+}
+
+/// Waits for the first or second answer, `None` when the player quits.
+fn read_choice() -> io::Result<Option<usize>> {
+    loop {
+        let Event::Key(KeyEvent {
+            code,
+            modifiers,
+            kind: KeyEventKind::Press,
+            ..
+        }) = event::read()?
+        else {
+            continue;
+        };
+        match code {
+            KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Left | KeyCode::Char('1') | KeyCode::Char('a') => return Ok(Some(0)),
+            KeyCode::Right | KeyCode::Char('2') | KeyCode::Char('d') => return Ok(Some(1)),
+            KeyCode::Esc | KeyCode::Char('q') => return Ok(None),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadState};
+use bevy::prelude::*;
+
+use flashcard_core::kanji::{parse_kanji, KanjiIndex};
+use flashcard_core::pronunciation::KanaClips;
+use flashcard_core::{parse_cards, parse_kana_clips, Card, Quiz, DECK_FILES};
+
+use crate::fonts::UiFont;
+use crate::session::StudySession;
+use crate::speech::KanaIndex;
+use crate::{write_sign, GameState, Gate, GateRng, KanjiInfo, QuizState, Sign};
+
+/// Folder of the deck files under the asset root.
+const DECK_ASSET_DIR: &str = "decks";
+
+/// Loads the deck files as assets and holds the game in `GameState::Loading`
+/// until they are ready. Saving a deck file while the game runs rebuilds the
+/// quiz, and cards that are still in the decks keep their weights.
+pub struct DeckAssets;
+
+impl Plugin for DeckAssets {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Deck>()
+            .init_asset_loader::<DeckLoader>()
+            .add_systems(Startup, load_decks)
+            .add_systems(
+                Update,
+                (
+                    wait_for_decks.run_if(in_state(GameState::Loading)),
+                    reload_decks.run_if(in_state(GameState::Running)),
+                ),
+            );
+    }
+}
+
+/// Text of a `.deck.toml` file. The files are parsed into cards together,
+/// since some decks are built from several of them.
+#[derive(Asset, TypePath, Debug)]
+pub struct Deck(String);
+
+#[derive(Default)]
+struct DeckLoader;
+
+impl AssetLoader for DeckLoader {
+    type Asset = Deck;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let content = String::from_utf8(bytes)?;
+        // a file that is not even TOML fails to load, so a reload keeps the old deck
+        content.parse::<toml::Table>()?;
+        Ok(Deck(content))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["deck.toml"]
+    }
+}
+
+/// Handles keeping every deck file loaded, by file name.
+#[derive(Resource)]
+pub struct DeckHandles(Vec<(&'static str, Handle<Deck>)>);
+
+impl DeckHandles {
+    fn read(&self, decks: &Assets<Deck>, file_name: &str) -> Result<String, String> {
+        self.0
+            .iter()
+            .find(|(name, _)| *name == file_name)
+            .and_then(|(_, handle)| decks.get(handle))
+            .map(|deck| deck.0.clone())
+            .ok_or_else(|| format!("{} is not loaded", file_name))
+    }
+
+    /// The cards of the session's decks.
+    pub fn cards(&self, decks: &Assets<Deck>, session: &StudySession) -> Result<Vec<Card>, String> {
+        parse_cards(
+            &session.study.decks,
+            &session.study.jlpt_levels,
+            &session.study.categories,
+            session.study.mode,
+            |file_name| self.read(decks, file_name),
+        )
+    }
+
+    pub fn kanji_index(&self, decks: &Assets<Deck>) -> Result<KanjiIndex, String> {
+        let content = self.read(decks, "kanji.deck.toml")?;
+        parse_kanji(&content)
+            .map(KanjiIndex::new)
+            .map_err(|err| format!("could not parse kanji.deck.toml: {}", err))
+    }
+
+    pub fn kana_clips(&self, decks: &Assets<Deck>) -> Result<KanaClips, String> {
+        parse_kana_clips(|file_name| self.read(decks, file_name))
+    }
+}
+
+fn load_decks(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DeckHandles(
+        DECK_FILES
+            .iter()
+            .map(|file_name| {
+                (
+                    *file_name,
+                    asset_server.load(format!("{}/{}", DECK_ASSET_DIR, file_name)),
+                )
+            })
+            .collect(),
+    ));
+
+    commands.spawn((Camera2d, StateScoped(GameState::Loading)));
+    commands.spawn((
+        Text::new("Loading decks..."),
+        TextFont {
+            font_size: 40.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(45.),
+            width: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        TextLayout::new_with_justify(JustifyText::Center),
+        StateScoped(GameState::Loading),
+    ));
+}
+
+/// Starts the run with the session's cards once every deck file is loaded
+/// and a font is picked, or closes the game if a deck could not be loaded or
+/// the session leaves too few cards to ask.
+#[allow(clippy::too_many_arguments)]
+fn wait_for_decks(
+    mut commands: Commands,
+    handles: Res<DeckHandles>,
+    ui_font: Option<Res<UiFont>>,
+    asset_server: Res<AssetServer>,
+    decks: Res<Assets<Deck>>,
+    session: Res<StudySession>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut exits: EventWriter<AppExit>,
+) {
+    let mut loaded = true;
+    for (file_name, handle) in &handles.0 {
+        match asset_server.load_state(handle) {
+            LoadState::Loaded => {}
+            LoadState::Failed(err) => {
+                error!("could not load {}: {}", file_name, err);
+                exits.send(AppExit::error());
+                return;
+            }
+            _ => loaded = false,
+        }
+    }
+    if !loaded || ui_font.is_none() {
+        return;
+    }
+    let built = handles
+        .cards(&decks, &session)
+        .and_then(Quiz::try_new)
+        .and_then(|quiz| Ok((quiz, handles.kanji_index(&decks)?)));
+    match built {
+        Ok((quiz, kanji_index)) => {
+            commands.insert_resource(QuizState(quiz));
+            commands.insert_resource(KanjiInfo(kanji_index));
+            next_state.set(GameState::Running);
+        }
+        Err(err) => {
+            error!("{}", err);
+            exits.send(AppExit::error());
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn reload_decks(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<Deck>>,
+    decks: Res<Assets<Deck>>,
+    handles: Res<DeckHandles>,
+    mut quiz: ResMut<QuizState>,
+    mut kanji_index: ResMut<KanjiInfo>,
+    mut kana_index: ResMut<KanaIndex>,
+    mut gate_query: Query<(&mut Gate, &Children)>,
+    signs_query: Query<&Sign>,
+    mut rng: ResMut<GateRng>,
+    session: Res<StudySession>,
+    fixed_time: Res<Time<Fixed>>,
+    ui_font: Res<UiFont>,
+) {
+    // the first load is what the run started with
+    let modified: Vec<&str> = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => handles
+                .0
+                .iter()
+                .find(|(_, handle)| handle.id() == *id)
+                .map(|(file_name, _)| *file_name),
+            _ => None,
+        })
+        .collect();
+    if modified.is_empty() {
+        return;
+    }
+
+    if modified.contains(&"kanji.deck.toml") {
+        match handles.kanji_index(&decks) {
+            Ok(index) => kanji_index.0 = index,
+            Err(err) => warn!("keeping the old kanji: {}", err),
+        }
+    }
+    if modified
+        .iter()
+        .any(|file_name| file_name.starts_with("hiragana"))
+    {
+        match handles.kana_clips(&decks) {
+            Ok(kana) => kana_index.0 = kana,
+            Err(err) => warn!("keeping the old kana clips: {}", err),
+        }
+    }
+
+    let cards = handles.cards(&decks, &session);
+    if let Err(err) = cards.and_then(|cards| quiz.replace_cards(cards)) {
+        warn!("keeping the old decks: {}", err);
+        return;
+    }
+    info!("reloaded the decks after {} changed", modified.join(", "));
+
+    // gates were asked with ids of the old cards, so ask them again
+    for (mut gate, children) in &mut gate_query {
+        let prompt = quiz.next_prompt(&mut rng.0);
+        let furigana = quiz.furigana(prompt.card, session.study.furigana);
+        for sign in signs_query.iter_many(children) {
+            write_sign(
+                &mut commands,
+                &ui_font,
+                sign,
+                quiz.card(prompt.card),
+                &prompt,
+                furigana,
+            );
+        }
+        gate.prompt = prompt;
+        gate.shown_at = fixed_time.elapsed();
+    }
+}
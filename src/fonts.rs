@@ -0,0 +1,141 @@
+use ab_glyph::{Font as _, FontRef};
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::settings::Settings;
+use crate::{setup, GameState, QuizState};
+
+/// Fonts tried in order for all text, as paths under the asset root. The
+/// first one that loads is used, so a missing CJK font falls back to the next.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct FontChain(pub Vec<String>);
+
+impl Default for FontChain {
+    fn default() -> Self {
+        FontChain(vec![
+            String::from("NotoSansJP-VariableFont_wght.ttf"),
+            String::from("MesloLGS NF Regular.ttf"),
+        ])
+    }
+}
+
+/// The font all text is drawn with, picked from the font chain while loading.
+#[derive(Resource, Deref)]
+pub struct UiFont(pub Handle<Font>);
+
+/// Every font of the chain, kept to suggest one that covers the decks.
+#[derive(Resource)]
+struct ChainFonts(Vec<(String, Handle<Font>)>);
+
+/// Loads the font chain during `GameState::Loading` and picks the font for all
+/// text. Once the run starts, warns about deck characters that font has no
+/// glyphs for, which would otherwise silently not show.
+pub struct Fonts;
+
+impl Plugin for Fonts {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_fonts)
+            .add_systems(
+                Update,
+                pick_font
+                    .run_if(in_state(GameState::Loading))
+                    .run_if(not(resource_exists::<UiFont>)),
+            )
+            .add_systems(
+                OnEnter(GameState::Running),
+                check_font_coverage.after(setup),
+            );
+    }
+}
+
+fn load_fonts(mut commands: Commands, settings: Res<Settings>, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ChainFonts(
+        settings
+            .fonts
+            .0
+            .iter()
+            .map(|path| (path.clone(), asset_server.load(path)))
+            .collect(),
+    ));
+}
+
+fn pick_font(mut commands: Commands, chain: Res<ChainFonts>, asset_server: Res<AssetServer>) {
+    for (index, (path, handle)) in chain.0.iter().enumerate() {
+        match asset_server.load_state(handle) {
+            LoadState::Loaded => {
+                if index > 0 {
+                    warn!(
+                        "{} could not be loaded, using {}; ./fetch_fonts.sh downloads the default CJK font",
+                        chain.0[0].0, path
+                    );
+                }
+                commands.insert_resource(UiFont(handle.clone()));
+                return;
+            }
+            LoadState::Failed(_) => {}
+            // an earlier font in the chain wins if it loads
+            _ => return,
+        }
+    }
+    warn!("none of the fonts in the settings could be loaded, using the default font");
+    commands.insert_resource(UiFont(Handle::default()));
+}
+
+fn check_font_coverage(
+    quiz: Res<QuizState>,
+    ui_font: Res<UiFont>,
+    chain: Res<ChainFonts>,
+    fonts: Res<Assets<Font>>,
+) {
+    let glyphs = |handle: &Handle<Font>| {
+        fonts
+            .get(handle)
+            .and_then(|font| FontRef::try_from_slice(&font.data).ok())
+    };
+    let Some(font) = glyphs(&ui_font) else {
+        return;
+    };
+
+    let missing: BTreeSet<char> = quiz
+        .cards()
+        .iter()
+        .flat_map(|card| {
+            [Some(&card.word), Some(&card.translation)]
+                .into_iter()
+                .chain([card.reading.as_ref(), card.lure.as_ref()])
+                .flatten()
+        })
+        .flat_map(|text| text.chars())
+        .filter(|c| !c.is_whitespace() && font.glyph_id(*c).0 == 0)
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+
+    let name = chain
+        .0
+        .iter()
+        .find(|(_, handle)| *handle == **ui_font)
+        .map_or("the default font", |(path, _)| path.as_str());
+    let sample: String = missing.iter().take(40).collect();
+    warn!(
+        "{} has no glyphs for {} characters in the decks, they will not show: {}{}",
+        name,
+        missing.len(),
+        sample,
+        if missing.len() > 40 { "..." } else { "" }
+    );
+
+    let covering = chain.0.iter().find(|(_, handle)| {
+        glyphs(handle).is_some_and(|font| missing.iter().all(|c| font.glyph_id(*c).0 != 0))
+    });
+    match covering {
+        Some((path, _)) => warn!("{} has them, put it first in the fonts in settings.toml", path),
+        None => warn!(
+            "add a font that has them to assets/ and the fonts in settings.toml, ./fetch_fonts.sh downloads Noto Sans JP"
+        ),
+    }
+}
@@ -1,6 +1,9 @@
 use bevy::app::App;
 use bevy::prelude::*;
 
+use crate::fonts::UiFont;
+use crate::GameState;
+
 #[derive(Component)]
 struct TextFeedBack;
 
@@ -19,22 +22,23 @@ impl Plugin for GameUI {
     fn build(&self, app: &mut App) {
         // app.init_resource::<MyOtherResource>();
         // app.add_event::<MyEvent>();
-        app.add_systems(Startup, plugin_init)
+        app.add_systems(OnEnter(GameState::Running), plugin_init)
             .insert_resource(UiInterface {
                 text_output: String::from("Hello"),
                 streak: 0,
             });
-        app.add_systems(Update, (update_text_feedback, update_streak_counter));
+        app.add_systems(
+            Update,
+            (update_text_feedback, update_streak_counter).run_if(in_state(GameState::Running)),
+        );
     }
 }
 
-fn plugin_init(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn plugin_init(mut commands: Commands, ui_font: Res<UiFont>) {
     commands.spawn((
         Text::new("hello\nplayer!"),
         TextFont {
-            // This font is loaded and will be used instead of the default font.
-            // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-            font: asset_server.load("NotoSansJP-Regular.ttf"),
+            font: ui_font.clone(),
             font_size: 50.0,
             ..default()
         },
@@ -52,9 +56,7 @@ fn plugin_init(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn((
         Text::new("default string"),
         TextFont {
-            // This font is loaded and will be used instead of the default font.
-            // font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-            font: asset_server.load("NotoSansJP-Regular.ttf"),
+            font: ui_font.clone(),
             font_size: 50.0,
             ..default()
         },
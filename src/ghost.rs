@@ -0,0 +1,256 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use std::fs;
+use std::path::PathBuf;
+
+use crate::replay::{Recording, Replay};
+use crate::session::StudySession;
+use crate::settings::Settings;
+use crate::{recenter_world, setup, DistanceTracker, GameState, Gate, Person};
+
+/// Where the best run of every seed and set of arguments is kept.
+const GHOST_DIR: &str = "ghosts";
+
+const GHOST_COLOR: Color = Color::srgba(124. / 255., 144. / 255., 1., 0.35);
+const GHOST_POST_COLOR: Color = Color::srgba(50. / 255., 50. / 255., 50. / 255., 0.35);
+
+/// Races the player against their best run on the same seed and arguments: a
+/// translucent cube follows the recorded lane positions, and a post beside
+/// every gate shows how the ghost answered it. Only runs with a fixed seed are
+/// raced, since any other run meets different gates. A raced run starts from
+/// the best run's card weights, so the same cards come up in the same order.
+pub struct GhostRace;
+
+impl Plugin for GhostRace {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(GameState::Running),
+            (load_best_run.before(setup), spawn_ghost.after(setup)),
+        )
+        .add_systems(Update, add_ghost_posts)
+        .add_systems(
+            FixedUpdate,
+            (move_ghost, ghost_gate_checker)
+                .chain()
+                .after(recenter_world),
+        )
+        .add_systems(Last, save_best_run);
+    }
+}
+
+/// The best run so far for this seed and arguments, replaced when the current
+/// run answers more gates right.
+#[derive(Resource)]
+pub struct BestRun {
+    path: PathBuf,
+    correct: usize,
+    recording: Option<Recording>,
+}
+
+impl BestRun {
+    pub fn recording(&self) -> Option<&Recording> {
+        self.recording.as_ref()
+    }
+}
+
+#[derive(Component)]
+struct Ghost {
+    /// Recorded z of every tick.
+    lane: Vec<f32>,
+    /// Recorded answers, in the order the gates were passed.
+    gates: Vec<bool>,
+    tick: usize,
+    passed: usize,
+}
+
+/// Shows the ghost's answer next to a gate's own colour.
+#[derive(Component)]
+struct GhostPost {
+    passed: bool,
+    material_handle: Handle<StandardMaterial>,
+}
+
+/// The best run file for a recording, named after its seed and arguments.
+fn best_run_path(recording: &Recording) -> PathBuf {
+    let mut name = format!("seed-{}", recording.seed);
+    let mut args = recording.args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            args.next();
+            continue;
+        }
+        for part in arg.split(|c: char| !c.is_alphanumeric()) {
+            if !part.is_empty() {
+                name.push('-');
+                name.push_str(part);
+            }
+        }
+    }
+    PathBuf::from(GHOST_DIR).join(name + ".toml")
+}
+
+fn load_best_run(
+    mut commands: Commands,
+    session: Res<StudySession>,
+    settings: Res<Settings>,
+    replay: Res<Replay>,
+) {
+    if replay.is_playing() || session.seed.or(settings.seed).is_none() {
+        return;
+    }
+    let Some(recording) = replay.recording() else {
+        return;
+    };
+
+    let path = best_run_path(recording);
+    let best = if path.exists() {
+        Recording::load(&path)
+            .map_err(|err| warn!("racing without a ghost: {}", err))
+            .ok()
+    } else {
+        None
+    };
+    commands.insert_resource(BestRun {
+        path,
+        correct: best.as_ref().map_or(0, Recording::correct_answers),
+        recording: best,
+    });
+}
+
+fn spawn_ghost(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    player_query: Query<&Mesh3d, With<Person>>,
+    best_run: Option<Res<BestRun>>,
+) {
+    let Some(best) = best_run.as_ref().and_then(|best| best.recording.as_ref()) else {
+        return;
+    };
+    info!(
+        "racing a ghost with {} correct answers",
+        best.correct_answers()
+    );
+    commands.spawn((
+        player_query.single().clone(),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: GHOST_COLOR,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })),
+        Transform::default(),
+        NotShadowCaster,
+        Ghost {
+            lane: best.lane_positions(),
+            gates: best.gate_results(),
+            tick: 0,
+            passed: 0,
+        },
+        Name::new("Ghost"),
+    ));
+}
+
+fn add_ghost_posts(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    gate_query: Query<Entity, Added<Gate>>,
+    ghost_query: Query<(), With<Ghost>>,
+) {
+    if ghost_query.is_empty() {
+        return;
+    }
+    for gate in &gate_query {
+        let material_handle = materials.add(StandardMaterial {
+            base_color: GHOST_POST_COLOR,
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        });
+        let post = commands
+            .spawn((
+                Mesh3d(meshes.add(Cylinder::new(0.1, 2.5))),
+                MeshMaterial3d(material_handle.clone()),
+                Transform::from_xyz(0., 0., 0.5),
+                NotShadowCaster,
+                GhostPost {
+                    passed: false,
+                    material_handle,
+                },
+            ))
+            .id();
+        commands.entity(gate).add_child(post);
+    }
+}
+
+fn move_ghost(
+    player_query: Query<&Transform, (With<DistanceTracker>, Without<Ghost>)>,
+    mut ghost_query: Query<(&mut Transform, &mut Ghost)>,
+) {
+    let Ok((mut transform, mut ghost)) = ghost_query.get_single_mut() else {
+        return;
+    };
+    // the ghost runs at the same speed, only its lane comes from the recording
+    transform.translation.x = player_query.single().translation.x;
+    if let Some(z) = ghost.lane.get(ghost.tick) {
+        transform.translation.z = *z;
+    }
+    ghost.tick += 1;
+}
+
+fn ghost_gate_checker(
+    mut ghost_query: Query<(&Transform, &mut Ghost)>,
+    gate_query: Query<(&Transform, &Children), With<Gate>>,
+    mut post_query: Query<&mut GhostPost>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let Ok((ghost_transform, mut ghost)) = ghost_query.get_single_mut() else {
+        return;
+    };
+    for (gate_transform, children) in &gate_query {
+        let reached = ghost_transform.translation.x >= gate_transform.translation.x;
+        let mut posts = post_query.iter_many_mut(children);
+        while let Some(mut post) = posts.fetch_next() {
+            let color = if reached && !post.passed {
+                let color = match ghost.gates.get(ghost.passed) {
+                    Some(true) => Color::srgba(0.2, 0.8, 0.2, 0.6),
+                    Some(false) => Color::srgba(0.8, 0.2, 0.2, 0.6),
+                    // the ghost's run ended before this gate
+                    None => GHOST_POST_COLOR,
+                };
+                ghost.passed += 1;
+                color
+            } else if !reached && post.passed {
+                // the gate was recycled to the front
+                GHOST_POST_COLOR
+            } else {
+                continue;
+            };
+            post.passed = reached;
+            if let Some(material) = materials.get_mut(&post.material_handle) {
+                material.base_color = color;
+            }
+        }
+    }
+}
+
+fn save_best_run(mut exits: EventReader<AppExit>, replay: Res<Replay>, best: Option<Res<BestRun>>) {
+    if exits.read().next().is_none() {
+        return;
+    }
+    let (Some(best), Some(recording)) = (best, replay.recording()) else {
+        return;
+    };
+    let correct = recording.correct_answers();
+    if correct <= best.correct {
+        return;
+    }
+    let saved = fs::create_dir_all(GHOST_DIR)
+        .map_err(|err| format!("could not create {}: {}", GHOST_DIR, err))
+        .and_then(|()| recording.save(&best.path));
+    match saved {
+        Ok(()) => info!(
+            "new best run with {} correct answers, saved to {}",
+            correct,
+            best.path.display()
+        ),
+        Err(err) => warn!("{}", err),
+    }
+}
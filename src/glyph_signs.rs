@@ -0,0 +1,446 @@
+use ab_glyph::{Font as _, FontRef, PxScale, ScaleFont};
+use bevy::app::App;
+use bevy::color::palettes::css::GOLD;
+use bevy::prelude::*;
+use bevy::render::{
+    mesh::{Indices, PrimitiveTopology},
+    render_asset::RenderAssetUsages,
+    render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use std::collections::HashMap;
+
+use crate::fonts::UiFont;
+use crate::session::{SignBackend, StudySession};
+use flashcard_core::furigana::RubySegment;
+
+/// Size glyphs are rasterized at in the atlas, signs scale them from there.
+const ATLAS_PX: f32 = 64.;
+const ATLAS_SIZE: u32 = 2048;
+
+// Sizes in sign pixels, the same units the render target signs use.
+const SIGN_PX: f32 = 512.;
+const LABEL_SIZE: f32 = 40.;
+const RUBY_SIZE: f32 = 40.;
+const TEXT_SIZE: f32 = 100.;
+
+/// Draws sign text straight into the 3D scene as quads sampling a shared glyph
+/// atlas, instead of rendering a UI tree to an image with a camera per sign.
+/// Enabled with `--signs glyph-atlas`.
+pub struct GlyphSigns;
+
+impl Plugin for GlyphSigns {
+    fn build(&self, app: &mut App) {
+        let backend = app
+            .world()
+            .get_resource::<StudySession>()
+            .map(|session| session.sign_backend);
+        if backend == Some(SignBackend::GlyphAtlas) {
+            app.init_resource::<GlyphAtlas>()
+                .add_systems(Update, layout_glyph_text.run_if(resource_exists::<UiFont>));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct AtlasGlyph {
+    /// Rectangle in the atlas, `None` for glyphs without an outline like spaces.
+    uv: Option<Rect>,
+    /// Glyph bounds relative to the pen position on the baseline, y pointing down.
+    bounds: Rect,
+    advance: f32,
+}
+
+#[derive(Resource)]
+pub struct GlyphAtlas {
+    image: Handle<Image>,
+    /// Material for the text quads, sampling the atlas.
+    pub text_material: Handle<StandardMaterial>,
+    /// Plain material for the sign board behind the text.
+    pub board_material: Handle<StandardMaterial>,
+    glyphs: HashMap<char, AtlasGlyph>,
+    cursor: UVec2,
+    row_height: u32,
+    /// Set when a glyph did not fit, the atlas is started over with the signs
+    /// still shown.
+    full: bool,
+}
+
+impl FromWorld for GlyphAtlas {
+    fn from_world(world: &mut World) -> Self {
+        let image = world.resource_mut::<Assets<Image>>().add(Image::new_fill(
+            Extent3d {
+                width: ATLAS_SIZE,
+                height: ATLAS_SIZE,
+                ..default()
+            },
+            TextureDimension::D2,
+            &[255, 255, 255, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        ));
+
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let text_material = materials.add(StandardMaterial {
+            base_color_texture: Some(image.clone()),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        });
+        let board_material = materials.add(StandardMaterial {
+            base_color: GOLD.into(),
+            reflectance: 0.02,
+            ..default()
+        });
+
+        GlyphAtlas {
+            image,
+            text_material,
+            board_material,
+            glyphs: HashMap::new(),
+            cursor: UVec2::ZERO,
+            row_height: 0,
+            full: false,
+        }
+    }
+}
+
+impl GlyphAtlas {
+    /// Returns the atlas entry for `c`, rasterizing it on first use. The atlas
+    /// image is only touched then, since every change uploads it to the GPU again.
+    fn glyph(&mut self, font: &FontRef, c: char, images: &mut Assets<Image>) -> AtlasGlyph {
+        if let Some(glyph) = self.glyphs.get(&c) {
+            return *glyph;
+        }
+
+        let scaled = font.as_scaled(PxScale::from(ATLAS_PX));
+        let glyph = scaled.scaled_glyph(c);
+        let advance = scaled.h_advance(glyph.id);
+
+        let mut atlas_glyph = AtlasGlyph {
+            uv: None,
+            bounds: Rect::default(),
+            advance,
+        };
+
+        if let Some(outlined) = font.outline_glyph(glyph) {
+            let bounds = outlined.px_bounds();
+            let (width, height) = (bounds.width() as u32, bounds.height() as u32);
+
+            // start a new row when the glyph does not fit, leaving a pixel of padding
+            if self.cursor.x + width + 1 > ATLAS_SIZE {
+                self.cursor = UVec2::new(0, self.cursor.y + self.row_height + 1);
+                self.row_height = 0;
+            }
+            if self.cursor.y + height + 1 > ATLAS_SIZE {
+                // drawn as a blank for now, and not kept so it is rasterized again
+                // once the atlas is cleared
+                self.full = true;
+                return atlas_glyph;
+            }
+
+            let Some(image) = images.get_mut(&self.image) else {
+                return atlas_glyph;
+            };
+            let origin = self.cursor;
+            outlined.draw(|x, y, coverage| {
+                let index = (((origin.y + y) * ATLAS_SIZE + origin.x + x) * 4) as usize;
+                image.data[index + 3] = (coverage.clamp(0., 1.) * 255.) as u8;
+            });
+
+            atlas_glyph.uv = Some(Rect::new(
+                origin.x as f32 / ATLAS_SIZE as f32,
+                origin.y as f32 / ATLAS_SIZE as f32,
+                (origin.x + width) as f32 / ATLAS_SIZE as f32,
+                (origin.y + height) as f32 / ATLAS_SIZE as f32,
+            ));
+            atlas_glyph.bounds = Rect::new(
+                bounds.min.x,
+                bounds.min.y,
+                bounds.min.x + width as f32,
+                bounds.min.y + height as f32,
+            );
+
+            self.cursor.x += width + 1;
+            self.row_height = self.row_height.max(height);
+        }
+
+        self.glyphs.insert(c, atlas_glyph);
+        atlas_glyph
+    }
+
+    /// Drops every glyph, leaving the whole atlas free again.
+    fn clear(&mut self, images: &mut Assets<Image>) {
+        if let Some(image) = images.get_mut(&self.image) {
+            for pixel in image.data.chunks_exact_mut(4) {
+                pixel[3] = 0;
+            }
+        }
+        self.glyphs.clear();
+        self.cursor = UVec2::ZERO;
+        self.row_height = 0;
+        self.full = false;
+    }
+}
+
+/// Text shown on a glyph atlas sign. The mesh on the same entity is rebuilt
+/// whenever a new `GlyphText` is inserted.
+#[derive(Component, Debug)]
+pub struct GlyphText {
+    label: Option<String>,
+    segments: Vec<RubySegment>,
+    laid_out: bool,
+}
+
+impl GlyphText {
+    pub fn new(label: Option<&str>, segments: Vec<RubySegment>) -> Self {
+        GlyphText {
+            label: label.map(str::to_owned),
+            segments,
+            laid_out: false,
+        }
+    }
+}
+
+struct Quad {
+    rect: Rect,
+    uv: Rect,
+    color: LinearRgba,
+}
+
+/// Lays out one line of text with its pen starting at `x` on `baseline`,
+/// returning the width of the line.
+#[allow(clippy::too_many_arguments)]
+fn push_line(
+    atlas: &mut GlyphAtlas,
+    font: &FontRef,
+    images: &mut Assets<Image>,
+    text: &str,
+    size: f32,
+    x: f32,
+    baseline: f32,
+    color: Color,
+    quads: &mut Vec<Quad>,
+) -> f32 {
+    let scale = size / ATLAS_PX;
+    let mut pen = x;
+    for c in text.chars() {
+        let glyph = atlas.glyph(font, c, images);
+        if let Some(uv) = glyph.uv {
+            quads.push(Quad {
+                // flip the glyph bounds from y down to y up
+                rect: Rect::new(
+                    pen + glyph.bounds.min.x * scale,
+                    baseline - glyph.bounds.max.y * scale,
+                    pen + glyph.bounds.max.x * scale,
+                    baseline - glyph.bounds.min.y * scale,
+                ),
+                uv,
+                color: color.into(),
+            });
+        }
+        pen += glyph.advance * scale;
+    }
+    pen - x
+}
+
+fn line_width(
+    atlas: &mut GlyphAtlas,
+    font: &FontRef,
+    images: &mut Assets<Image>,
+    text: &str,
+) -> f32 {
+    text.chars()
+        .map(|c| atlas.glyph(font, c, images).advance)
+        .sum::<f32>()
+        / ATLAS_PX
+}
+
+/// Builds the text mesh: the label, then the furigana row, then the text,
+/// centred on the sign and shrunk to fit when it is too wide.
+fn layout(
+    atlas: &mut GlyphAtlas,
+    font: &FontRef,
+    images: &mut Assets<Image>,
+    text: &GlyphText,
+) -> Mesh {
+    let scaled = font.as_scaled(PxScale::from(ATLAS_PX));
+    let (ascent, line_height) = (
+        scaled.ascent() / ATLAS_PX,
+        (scaled.ascent() - scaled.descent()) / ATLAS_PX,
+    );
+
+    let mut quads = Vec::new();
+    let mut y = 0.;
+
+    if let Some(label) = &text.label {
+        let width = line_width(atlas, font, images, label) * LABEL_SIZE;
+        let grey = Color::srgb(0.3, 0.3, 0.3);
+        push_line(
+            atlas,
+            font,
+            images,
+            label,
+            LABEL_SIZE,
+            -width / 2.,
+            y - ascent * LABEL_SIZE,
+            grey,
+            &mut quads,
+        );
+        y -= line_height * LABEL_SIZE;
+    }
+
+    let columns: Vec<(f32, f32)> = text
+        .segments
+        .iter()
+        .map(|segment| {
+            let base = line_width(atlas, font, images, &segment.base) * TEXT_SIZE;
+            let ruby = segment
+                .ruby
+                .as_deref()
+                .map(|ruby| line_width(atlas, font, images, ruby) * RUBY_SIZE)
+                .unwrap_or_default();
+            (base, ruby)
+        })
+        .collect();
+    let has_ruby = text.segments.iter().any(|segment| segment.ruby.is_some());
+    let total_width: f32 = columns.iter().map(|(base, ruby)| base.max(*ruby)).sum();
+
+    let ruby_baseline = y - ascent * RUBY_SIZE;
+    if has_ruby {
+        y -= line_height * RUBY_SIZE;
+    }
+    let text_baseline = y - ascent * TEXT_SIZE;
+    y -= line_height * TEXT_SIZE;
+
+    let mut x = -total_width / 2.;
+    for (segment, (base_width, ruby_width)) in text.segments.iter().zip(&columns) {
+        let column_width = base_width.max(*ruby_width);
+        if let Some(ruby) = &segment.ruby {
+            push_line(
+                atlas,
+                font,
+                images,
+                ruby,
+                RUBY_SIZE,
+                x + (column_width - ruby_width) / 2.,
+                ruby_baseline,
+                Color::BLACK,
+                &mut quads,
+            );
+        }
+        push_line(
+            atlas,
+            font,
+            images,
+            &segment.base,
+            TEXT_SIZE,
+            x + (column_width - base_width) / 2.,
+            text_baseline,
+            Color::BLACK,
+            &mut quads,
+        );
+        x += column_width;
+    }
+
+    // centre the block vertically and fit it inside the sign face
+    let height = -y;
+    let block_width = quads
+        .iter()
+        .map(|quad| quad.rect.min.x.abs().max(quad.rect.max.x.abs()) * 2.)
+        .fold(0., f32::max);
+    let fit = (SIGN_PX * 0.9 / block_width)
+        .min(SIGN_PX * 0.6 / height)
+        .min(1.);
+
+    let mut positions = Vec::with_capacity(quads.len() * 4);
+    let mut uvs = Vec::with_capacity(quads.len() * 4);
+    let mut colors = Vec::with_capacity(quads.len() * 4);
+    let mut indices = Vec::with_capacity(quads.len() * 6);
+    for quad in &quads {
+        let (min, max) = (
+            Vec2::new(quad.rect.min.x, quad.rect.min.y + height / 2.) * fit,
+            Vec2::new(quad.rect.max.x, quad.rect.max.y + height / 2.) * fit,
+        );
+        let start = positions.len() as u32;
+        positions.extend([
+            [min.x, min.y, 0.],
+            [max.x, min.y, 0.],
+            [max.x, max.y, 0.],
+            [min.x, max.y, 0.],
+        ]);
+        uvs.extend([
+            [quad.uv.min.x, quad.uv.max.y],
+            [quad.uv.max.x, quad.uv.max.y],
+            [quad.uv.max.x, quad.uv.min.y],
+            [quad.uv.min.x, quad.uv.min.y],
+        ]);
+        colors.extend([quad.color.to_f32_array(); 4]);
+        indices.extend([start, start + 1, start + 2, start, start + 2, start + 3]);
+    }
+
+    let normals = vec![[0., 0., 1.]; positions.len()];
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Transform of a sign's text mesh relative to the sign, a sign pixel wide
+/// per unit and sitting just in front of the face the player looks at.
+pub fn glyph_text_transform() -> Transform {
+    Transform {
+        translation: Vec3::new(-0.51, 0., 0.),
+        rotation: Quat::from_mat3(&Mat3::from_cols(Vec3::NEG_Y, Vec3::Z, Vec3::NEG_X)),
+        scale: Vec3::splat(4. / SIGN_PX),
+    }
+}
+
+fn layout_glyph_text(
+    mut texts: Query<(&mut GlyphText, &Mesh3d)>,
+    fonts: Res<Assets<Font>>,
+    ui_font: Res<UiFont>,
+    mut atlas: ResMut<GlyphAtlas>,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if texts.iter().all(|(text, _)| text.laid_out) {
+        return;
+    }
+    let Some(font) = fonts.get(&**ui_font) else {
+        return;
+    };
+    let Ok(font) = FontRef::try_from_slice(&font.data) else {
+        return;
+    };
+
+    for (mut text, mesh) in &mut texts {
+        if text.laid_out {
+            continue;
+        }
+        let mesh_data = layout(&mut atlas, &font, &mut images, &text);
+        meshes.insert(&mesh.0, mesh_data);
+        text.laid_out = true;
+    }
+
+    // glyphs of signs long gone fill the atlas, so start it over and lay out
+    // the signs that are still shown again
+    if atlas.full {
+        atlas.clear(&mut images);
+        for (mut text, mesh) in &mut texts {
+            let mesh_data = layout(&mut atlas, &font, &mut images, &text);
+            meshes.insert(&mesh.0, mesh_data);
+            text.laid_out = true;
+        }
+        if atlas.full {
+            warn!("the signs shown need more glyphs than the atlas holds, some are drawn blank");
+            atlas.full = false;
+        }
+    }
+}
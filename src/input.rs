@@ -0,0 +1,168 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::settings::Settings;
+
+/// Something the player can do, whatever key or button it is bound to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    LaneLeft,
+    LaneRight,
+    Pause,
+    RevealFurigana,
+    Settings,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 6] = [
+        Action::LaneLeft,
+        Action::LaneRight,
+        Action::Pause,
+        Action::RevealFurigana,
+        Action::Settings,
+        Action::Quit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::LaneLeft => "Left lane",
+            Action::LaneRight => "Right lane",
+            Action::Pause => "Pause",
+            Action::RevealFurigana => "Reveal furigana",
+            Action::Settings => "Settings",
+            Action::Quit => "Quit",
+        }
+    }
+}
+
+/// A key, mouse button or gamepad button that triggers an action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton),
+}
+
+impl Binding {
+    /// Whether both bindings are on the same kind of device.
+    pub fn same_device(&self, other: &Binding) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse {:?}", button),
+            Binding::Gamepad(button) => format!("Pad {:?}", button),
+        }
+    }
+}
+
+/// The bindings of every action, saved in the `[input]` table of the settings file:
+///
+/// ```toml
+/// [input]
+/// lane_left = [{ key = "ArrowLeft" }, { key = "KeyA" }, { gamepad = "DPadLeft" }]
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct InputMap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::*;
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let defaults = match action {
+                    Action::LaneLeft => vec![
+                        Key(KeyCode::ArrowLeft),
+                        Key(KeyCode::KeyA),
+                        Gamepad(GamepadButton::DPadLeft),
+                    ],
+                    Action::LaneRight => vec![
+                        Key(KeyCode::ArrowRight),
+                        Key(KeyCode::KeyD),
+                        Gamepad(GamepadButton::DPadRight),
+                    ],
+                    Action::Pause => vec![Key(KeyCode::KeyP), Gamepad(GamepadButton::Start)],
+                    Action::RevealFurigana => {
+                        vec![Key(KeyCode::KeyF), Gamepad(GamepadButton::North)]
+                    }
+                    Action::Settings => vec![Key(KeyCode::F1), Gamepad(GamepadButton::Select)],
+                    Action::Quit => vec![Key(KeyCode::Escape)],
+                };
+                (action, defaults)
+            })
+            .collect();
+        InputMap { bindings }
+    }
+}
+
+impl InputMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Binds `binding` to `action` in place of the action's bindings on the same
+    /// device, and takes it away from any other action it was bound to.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        for bindings in self.bindings.values_mut() {
+            bindings.retain(|bound| *bound != binding);
+        }
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|bound| !bound.same_device(&binding));
+        bindings.push(binding);
+    }
+
+    /// Gives actions missing from a settings file their default bindings.
+    pub fn fill_defaults(&mut self) {
+        for (action, defaults) in InputMap::default().bindings {
+            self.bindings.entry(action).or_insert(defaults);
+        }
+    }
+}
+
+/// Feeds `ButtonInput<Action>` from the bound keys and buttons, so gameplay
+/// systems ask for actions instead of hardcoded keys.
+pub struct ActionInput;
+
+impl Plugin for ActionInput {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ButtonInput<Action>>()
+            .add_systems(PreUpdate, update_actions.after(InputSystem));
+    }
+}
+
+fn update_actions(
+    settings: Res<Settings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut actions: ResMut<ButtonInput<Action>>,
+) {
+    actions.clear();
+    for action in Action::ALL {
+        let pressed = settings
+            .input
+            .bindings(action)
+            .iter()
+            .any(|binding| match binding {
+                Binding::Key(key) => keyboard.pressed(*key),
+                Binding::Mouse(button) => mouse.pressed(*button),
+                Binding::Gamepad(button) => gamepads.iter().any(|gamepad| gamepad.pressed(*button)),
+            });
+        if pressed {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
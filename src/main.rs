@@ -8,175 +8,242 @@ use bevy::{
     text::FontSmoothing,
     window::PrimaryWindow,
 };
-use bevy::{pbr::NotShadowCaster, render::camera::RenderTarget};
+use bevy::{
+    ecs::{component::ComponentId, world::DeferredWorld},
+    pbr::NotShadowCaster,
+    render::camera::RenderTarget,
+};
 #[allow(unused_imports)]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 use std::f32::consts::PI;
+use std::time::Duration;
 
-use serde::Deserialize;
-use std::fs;
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 
-use rand::distributions::{Distribution, WeightedIndex};
-use rand::seq::SliceRandom;
-use rand::{thread_rng, Rng};
+use flashcard_core::furigana::*;
+use flashcard_core::kanji::*;
+use flashcard_core::*;
 
 mod game_ui;
 use game_ui::*;
 
+mod session;
+use session::*;
+
+mod glyph_signs;
+use glyph_signs::*;
+
+mod input;
+use input::*;
+
+mod settings;
+use settings::{RunGameplay, Settings};
+
+mod settings_menu;
+use settings_menu::*;
+
+mod replay;
+use replay::*;
+
+mod ghost;
+use ghost::*;
+
+mod decks;
+use decks::*;
+
+mod fonts;
+use fonts::*;
+
+mod speech;
+use speech::*;
+
+#[cfg(test)]
+mod tests;
+
 #[derive(Component)]
 struct Person;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
+#[component(on_remove = despawn_sign_ui)]
 struct Sign {
-    image_handle: Handle<Image>,
-    ui_id: Entity,
+    target: SignTarget,
+    slot: SignSlot,
+}
+
+/// Frees a render target sign's UI root, which lives outside the gate hierarchy,
+/// whenever the sign is despawned.
+fn despawn_sign_ui(mut world: DeferredWorld, entity: Entity, _: ComponentId) {
+    let Some(SignTarget::Ui(ui_id)) = world.get::<Sign>(entity).map(|sign| sign.target) else {
+        return;
+    };
+    if world.entities().contains(ui_id) {
+        world.commands().entity(ui_id).despawn_recursive();
+    }
+}
+
+/// What a sign's text is drawn with, see `SignBackend`.
+#[derive(Clone, Copy)]
+enum SignTarget {
+    /// UI root rendered to the sign's image by its own camera.
+    Ui(Entity),
+    /// Child mesh built from the shared glyph atlas.
+    Glyphs(Entity),
+}
+
+/// Where a sign hangs on its gate.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SignSlot {
+    Left,
+    Prompt,
+    Right,
 }
 
 enum GateState {
     Passed,
     Unpass,
 }
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum CorrectSide {
     Left,
     Right,
 }
 
+impl CorrectSide {
+    /// Index of the choice shown on this side's sign.
+    fn choice(self) -> usize {
+        match self {
+            CorrectSide::Left => 0,
+            CorrectSide::Right => 1,
+        }
+    }
+}
+
 #[derive(Component)]
 struct Gate {
-    word: Word,
+    prompt: Prompt,
     gate_state: GateState,
-    correct_side: CorrectSide,
     material_handle: Handle<StandardMaterial>,
+    /// Fixed time at which the prompt went up, to measure how long answering took.
+    shown_at: Duration,
 }
 
-#[derive(Component)]
-struct DistanceTracker {
-    distance_traveled: f32,
-    _distance_from_last_sign: f32,
+/// Sent when the player passes a gate, for the systems that react to answers
+/// instead of `gate_pass_checker` doing it all.
+#[derive(Event, Debug, Clone)]
+struct GatePassed {
+    gate: Entity,
+    /// The prompt the gate asked, the gate itself may already show another one.
+    prompt: Prompt,
+    chosen_lane: CorrectSide,
+    correct: bool,
+    /// Time from the prompt going up to the player passing the gate.
+    latency: Duration,
+    /// Player z the lane was read from.
+    z: f32,
 }
 
-// const ADVANCE_AMOUNT_PER_STEP: f32 = 0.06;
-// const ADVANCE_AMOUNT_PER_STEP: f32 = 0.1;
-// const ADVANCE_AMOUNT_PER_STEP: f32 = 0.15;
-// const ADVANCE_AMOUNT_PER_STEP: f32 = 0.22;
-const ADVANCE_AMOUNT_PER_STEP: f32 = 0.2;
-const SIGN_SPACING_DISTANCE: f32 = 25.;
-const NUMBER_OF_SIGNS: u32 = 4;
-
-#[derive(Debug, Deserialize, Clone)]
-enum Category {
-    Adjective,
-    Adverb,
-    Noun,
-    Pronoun, // Add this line
-    Verb,
-    Time,
-    Question,
-    Response,
-    Conjunction,       // e.g., "and," "but"
-    Interjection,      // e.g., "yes," "no," "thank you"
-    PreNounAdjectival, // modifiers before nouns, like "that"
-    SuruVerb,          // nouns that can be used with "suru" to make verbs (e.g., 勉強する)
-}
-
-// enum Category {
-//     Adjective,
-//     Noun,
-//     Expression,
-//     Verb,
-//     Time,
-//     Question,
-//     Response,
-// }
+/// The quiz the gates are asked from.
+#[derive(Resource, Deref, DerefMut)]
+struct QuizState(Quiz);
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize, Clone)]
-struct FullWord {
-    japanese_word: String,
-    furigana: String,
-    english_translation: String,
-    category: Category,
-    romaji: String,
-}
-
-#[allow(dead_code)]
-#[derive(Debug, Deserialize, Clone)]
-struct JlptWord {
-    original: String,
-    furigana: String,
-    english: String,
-    jlpt_level: String,
-}
+/// Draws the card, wrong answer and side of every gate. Seeded, so the same
+/// seed gives the same gates on any machine.
+#[derive(Resource)]
+struct GateRng(ChaCha8Rng);
 
-#[derive(Debug, Deserialize, Resource)]
-struct Vocabulary {
-    translations: Vec<FullWord>,
-}
+/// Kanji breakdowns shown with the answer after each gate.
+#[derive(Resource, Deref)]
+struct KanjiInfo(KanjiIndex);
 
-#[allow(dead_code)]
-#[derive(Debug, Deserialize, Clone)]
-struct Hiragana {
-    character: String,
-    romaji: String,
+#[derive(Component)]
+struct GroundTile;
+
+/// The lane the player steers towards when playing with lane control.
+#[derive(Resource)]
+struct LaneControl {
+    side: CorrectSide,
+    /// Whether the left stick was pushed to the side last frame.
+    stick_held: bool,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct Word {
-    word: String,
-    translation: String,
+#[derive(Component)]
+struct DistanceTracker {
+    /// Total distance of the run. World positions are re-centered now and then by
+    /// `recenter_world`, so this is the only place the full distance is kept.
+    distance_traveled: f64,
+    _distance_from_last_sign: f32,
 }
 
-#[derive(Debug, Resource)]
-struct WordList {
-    words: Vec<Word>,
-    weights: Vec<f32>,
+/// Gameplay waits in `Loading` until the decks are loaded.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum GameState {
+    #[default]
+    Loading,
+    Running,
 }
 
-impl WordList {
-    #[allow(dead_code)]
-    fn ramdom_word(&self) -> &Word {
-        // Create a random number generator
-        let mut rng = thread_rng();
-
-        // Choose a random element from the vector
-        self.words.choose(&mut rng).expect("vec is empty")
-    }
-
-    #[allow(dead_code)]
-    fn ramdom_word_pair(&self) -> (&Word, &Word) {
-        let mut rng = thread_rng();
-
-        // Choose two random elements from the vector
-        let chosen: Vec<&Word> = self.words.choose_multiple(&mut rng, 2).collect();
+/// Distance ahead of the start where the first gate goes.
+const FIRST_GATE_DISTANCE: f32 = 25.;
+/// Rows of ground tiles kept around the player, even so that moving a row to the
+/// front keeps the chessboard pattern.
+const GROUND_ROWS: i32 = 100;
+const GROUND_ROWS_BEHIND: i32 = 3;
+const GROUND_TILE_SIZE: f32 = 2.;
+/// How far the player may get from the origin before the world is shifted back,
+/// keeping f32 positions precise on long runs.
+const RECENTER_DISTANCE: f32 = 1000.;
+/// Distance of the lane centres from the middle of the track.
+const LANE_OFFSET: f32 = 2.;
+/// How quickly the player slides over to a new lane, higher is snappier.
+const LANE_SWITCH_RATE: f32 = 12.;
+const STICK_THRESHOLD: f32 = 0.5;
+const GATE_COLOR: Color = Color::srgb(50. / 255., 50. / 255., 50. / 255.);
 
-        // Unwrap the first two chosen elements or panic if not enough elements
-        match chosen.as_slice() {
-            [first, second] => (*first, *second),
-            _ => panic!("Not enough elements in translations vector"),
-        }
+fn main() {
+    fn exit_with(err: String) -> ! {
+        eprintln!("{}", err);
+        std::process::exit(2);
     }
 
-    fn get_weighted_word_pair(&self) -> (&Word, &Word) {
-        let mut rng = thread_rng();
-        let distribution = WeightedIndex::new(&self.weights).unwrap();
-
-        let word = &self.words[distribution.sample(&mut rng)];
-        let mut word_2 = self.ramdom_word();
-        // ovoid duplicate
-        while word == word_2 {
-            word_2 = self.ramdom_word();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut session =
+        StudySession::from_args(args.iter().cloned()).unwrap_or_else(|err| exit_with(err));
+    let replay = match (&session.replay, &session.record) {
+        (Some(path), _) => {
+            let replay = Replay::play(path).unwrap_or_else(|err| exit_with(err));
+            let recording = replay.recording().expect("a replay has a recording");
+            session = StudySession::from_args(recording.args.iter().cloned())
+                .unwrap_or_else(|err| exit_with(err));
+            session.seed = Some(recording.seed);
+            replay
         }
+        (None, path) => Replay::record(path.clone(), &args),
+    };
 
-        (word, word_2)
-    }
-}
-
-fn main() {
     App::new()
+        .insert_resource(session)
+        .insert_resource(replay)
+        .insert_resource(LaneControl {
+            side: CorrectSide::Left,
+            stick_held: false,
+        })
         .add_plugins(DefaultPlugins)
+        .init_state::<GameState>()
+        .enable_state_scoped_entities::<GameState>()
         .add_plugins(GameUI)
+        .add_plugins(GlyphSigns)
+        .add_plugins((
+            ActionInput,
+            SettingsMenu,
+            Replays,
+            GhostRace,
+            DeckAssets,
+            Fonts,
+            Speech,
+        ))
         // .add_plugins(WorldInspectorPlugin::new())
+        .add_plugins(Track)
         .add_plugins(FpsOverlayPlugin {
             config: FpsOverlayConfig {
                 enabled: true,
@@ -191,82 +258,110 @@ fn main() {
                 },
             },
         })
-        .add_systems(Startup, setup)
-        .add_systems(
-            Update,
-            (
-                sign_spawn_manager,
-                close_on_esc,
-                gate_pass_checker,
-                // resource_debug_system,
-            ),
-        )
-        .add_systems(FixedUpdate, (move_distance_marker, move_player))
         .run();
 }
 
+/// The run itself: sets up the track once the decks are loaded, then moves the
+/// player and checks, scores and recycles the gates.
+struct Track;
+
+impl Plugin for Track {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GatePassed>()
+            .add_systems(
+                OnEnter(GameState::Running),
+                (setup, (apply_gameplay_settings, show_fps_overlay)).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    lane_input,
+                    (touch_input, close_on_esc, toggle_pause).run_if(settings_menu_closed),
+                    (
+                        ground_recycler,
+                        reveal_furigana,
+                        show_fps_overlay.run_if(resource_changed::<Settings>),
+                        save_progress.run_if(resource_exists_and_changed::<QuizState>),
+                    )
+                        .run_if(in_state(GameState::Running)),
+                    // resource_debug_system,
+                ),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    move_distance_marker,
+                    // gates are checked and recycled on fixed ticks too, so a replayed
+                    // run meets every gate at the same tick it was recorded at
+                    (
+                        take_gameplay_settings,
+                        (apply_gameplay_settings, sync_gate_count)
+                            .run_if(resource_changed::<RunGameplay>),
+                        move_player,
+                        recenter_world,
+                        gate_pass_checker,
+                        score_gate,
+                        (color_gate, show_gate_feedback, record_gate, log_gate),
+                        sign_spawn_manager,
+                    )
+                        .chain(),
+                )
+                    .run_if(in_state(GameState::Running)),
+            );
+    }
+}
+
 /// set up a simple 3D scene
+#[allow(clippy::too_many_arguments)]
 fn setup(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut images: ResMut<Assets<Image>>,
-    mut asset_server: Res<AssetServer>,
+    ui_font: Res<UiFont>,
+    session: Res<StudySession>,
+    settings: Res<Settings>,
+    mut replay: ResMut<Replay>,
+    glyph_atlas: Option<Res<GlyphAtlas>>,
+    mut quiz: ResMut<QuizState>,
+    best_run: Option<Res<BestRun>>,
+    fixed_time: Res<Time<Fixed>>,
 ) {
-    #[allow(unused_variables)]
-    let vocabulary_full = read_full_translation_file("dictionary/jlpt_vocab.toml");
-
-    let mut vocabulary = read_translation_file("dictionary/N5_translations_furigana.toml");
-    // let vocabulary = read_translation_file("N5_transtlations.toml");
-
-    // let extra_vocabulary = read_translation_file("dictionary/translations.toml");
-    let extra_vocabulary = read_translation_file("dictionary/translations_furigana.toml");
-    vocabulary
-        .translations
-        .extend(extra_vocabulary.translations);
-
-    let mut hiragana_list = read_hiragana_file("dictionary/hiragana.toml");
-    hiragana_list.extend(read_hiragana_file("dictionary/hiragana_dakuten.toml"));
-
-    hiragana_list.extend(read_hiragana_file("dictionary/hiragana_handakuken.toml"));
-
-    let mut new_list = WordList {
-        words: Vec::new(),
-        weights: Vec::new(),
-    };
-
-    // for translation in vocabulary_full.words {
-    //     new_list.words.push(Word {
-    //         word: translation.english.clone(),
-    //         translation: translation.original.clone(),
-    //     });
-    // }
-    //
-
-    // for translation in vocabulary.translations {
-    //     new_list.words.push(Word {
-    //         word: translation.furigana.clone(),
-    //         translation: translation.romaji.clone(),
-    //     });
-    // }
-
-    for hiragana in hiragana_list {
-        new_list.words.push(Word {
-            word: hiragana.character.clone(),
-            translation: hiragana.romaji.clone(),
-        });
+    let fixed_seed = session.seed.or(settings.seed);
+    let seed = fixed_seed.unwrap_or_else(|| thread_rng().gen());
+    info!("gate seed {}", seed);
+    let mut rng = GateRng(ChaCha8Rng::seed_from_u64(seed));
+
+    let followed = replay
+        .played_back()
+        .or(best_run.as_ref().and_then(|best| best.recording()));
+    // a replay or ghost race plays with the settings of its recording
+    let gameplay = followed.map_or(settings.gameplay, Recording::gameplay);
+    match followed.map(Recording::progress) {
+        Some(progress) => quiz.restore(progress),
+        // a seed gives the same gates on any machine, so it starts from fresh
+        // weights instead of this machine's progress.toml
+        None if fixed_seed.is_some() => {}
+        None => match Progress::load(PROGRESS_FILE) {
+            Ok(progress) => quiz.restore(&progress),
+            Err(err) => warn!("starting without saved progress: {}", err),
+        },
     }
-
-    // set all weights to 1.0
-    new_list.weights.resize(new_list.words.len(), 1.0);
+    replay.begin(seed, &quiz, gameplay);
 
     // Chessboard Planetrasnlations
     let black_material = materials.add(Color::BLACK);
     let white_material = materials.add(Color::WHITE);
 
-    let plane_mesh = meshes.add(Plane3d::default().mesh().size(2.0, 2.0));
+    let plane_mesh = meshes.add(
+        Plane3d::default()
+            .mesh()
+            .size(GROUND_TILE_SIZE, GROUND_TILE_SIZE),
+    );
 
-    for x in -3..1500 {
+    // only a window of rows around the player exists, `ground_recycler` moves the rows
+    // that fall behind to the front
+    for x in -GROUND_ROWS_BEHIND..GROUND_ROWS - GROUND_ROWS_BEHIND {
         for z in -2..3 {
             commands.spawn((
                 Mesh3d(plane_mesh.clone()),
@@ -275,8 +370,13 @@ fn setup(
                 } else {
                     white_material.clone()
                 }),
-                Transform::from_xyz(x as f32 * 2.0, -1.0, z as f32 * 2.0),
+                Transform::from_xyz(
+                    x as f32 * GROUND_TILE_SIZE,
+                    -1.0,
+                    z as f32 * GROUND_TILE_SIZE,
+                ),
                 NotShadowCaster,
+                GroundTile,
             ));
         }
     }
@@ -377,102 +477,272 @@ fn setup(
     ));
 
     // spawn first sign
-    for i in 0..3 {
-        let (main_translation, off_translation) = new_list.get_weighted_word_pair();
+    for i in 0..gameplay.gates {
+        let prompt = quiz.next_prompt(&mut rng.0);
         spawn_gate(
             &mut commands,
             &mut meshes,
             &mut materials,
             &mut images,
-            main_translation,
-            off_translation,
-            25. + i as f32 * SIGN_SPACING_DISTANCE,
-            &mut asset_server,
+            &quiz,
+            prompt,
+            session.study.furigana,
+            FIRST_GATE_DISTANCE + i as f32 * gameplay.gate_spacing,
+            // the fixed clock ran through loading, latency counts from here
+            fixed_time.elapsed(),
+            &ui_font,
+            glyph_atlas.as_deref(),
         );
     }
 
-    commands.insert_resource(new_list);
+    commands.insert_resource(RunGameplay(gameplay));
+    commands.insert_resource(rng);
 }
 
+#[allow(clippy::too_many_arguments)]
 fn move_player(
     q_windows: Query<&Window, With<PrimaryWindow>>,
     mut query: Query<&mut Transform, With<Person>>,
+    touches: Res<Touches>,
+    session: Res<StudySession>,
+    lane_control: Res<LaneControl>,
+    settings: Res<Settings>,
+    gameplay: Res<RunGameplay>,
+    time: Res<Time>,
+    mut replay: ResMut<Replay>,
 ) {
+    let replayed_z = replay.lane_position();
     for mut transform in &mut query {
         transform.translation += Vec3 {
-            x: ADVANCE_AMOUNT_PER_STEP,
+            x: gameplay.speed,
             y: 0.,
             z: 0.,
         };
-        // let mouse_pos = cursor_moved_events.iter().last();
-        let window = q_windows.single();
-        if let Some(position) = window.cursor_position() {
-            let motion_width = 8.;
-            let z = position.x / window.width() * motion_width - motion_width / 2.;
+        if let Some(z) = replayed_z {
             transform.translation.z = z;
+            continue;
+        }
+        match session.control.unwrap_or(settings.control) {
+            ControlScheme::Analog => {
+                // let mouse_pos = cursor_moved_events.iter().last();
+                let window = q_windows.single();
+                // a finger on the screen steers like the cursor
+                let position = touches
+                    .first_pressed_position()
+                    .or_else(|| window.cursor_position());
+                if let Some(position) = position {
+                    let motion_width = gameplay.steering_width;
+                    let z = position.x / window.width() * motion_width - motion_width / 2.;
+                    transform.translation.z = z;
+                }
+            }
+            ControlScheme::Lanes => {
+                let target_z = match lane_control.side {
+                    CorrectSide::Left => -LANE_OFFSET,
+                    CorrectSide::Right => LANE_OFFSET,
+                };
+                // ease towards the lane, independent of the fixed timestep
+                let t = 1. - (-LANE_SWITCH_RATE * time.delta_secs()).exp();
+                transform.translation.z += (target_z - transform.translation.z) * t;
+            }
+        }
+    }
+    if let Some(transform) = query.iter().last() {
+        replay.end_tick(transform.translation.z);
+    }
+}
+
+/// Switches lanes on the lane actions or a flick of the left stick.
+fn lane_input(
+    actions: Res<ButtonInput<Action>>,
+    gamepads: Query<&Gamepad>,
+    mut lane_control: ResMut<LaneControl>,
+) {
+    let mut left = actions.just_pressed(Action::LaneLeft);
+    let mut right = actions.just_pressed(Action::LaneRight);
+
+    let mut stick_x: f32 = 0.;
+    for gamepad in &gamepads {
+        if gamepad.left_stick().x.abs() > stick_x.abs() {
+            stick_x = gamepad.left_stick().x;
+        }
+    }
+
+    // the stick has to come back to the centre before it switches lanes again
+    let stick_pushed = stick_x.abs() > STICK_THRESHOLD;
+    if stick_pushed && !lane_control.stick_held {
+        left |= stick_x < 0.;
+        right |= stick_x > 0.;
+    }
+    lane_control.stick_held = stick_pushed;
+
+    if left && !right {
+        lane_control.side = CorrectSide::Left;
+    } else if right && !left {
+        lane_control.side = CorrectSide::Right;
+    }
+}
+
+/// Picks the lane on the half of the screen being touched, so tapping a side
+/// moves there and dragging a finger across the middle switches lanes.
+fn touch_input(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    touches: Res<Touches>,
+    mut lane_control: ResMut<LaneControl>,
+) {
+    let Some(position) = touches.first_pressed_position() else {
+        return;
+    };
+    lane_control.side = if position.x < q_windows.single().width() / 2. {
+        CorrectSide::Left
+    } else {
+        CorrectSide::Right
+    };
+}
+
+fn ground_recycler(
+    player_query: Query<&Transform, (With<Person>, With<DistanceTracker>)>,
+    mut tile_query: Query<&mut Transform, (With<GroundTile>, Without<Person>)>,
+) {
+    let player_x = player_query.single().translation.x;
+    let behind = player_x - GROUND_ROWS_BEHIND as f32 * GROUND_TILE_SIZE;
+    for mut transform in &mut tile_query {
+        while transform.translation.x < behind {
+            transform.translation.x += GROUND_ROWS as f32 * GROUND_TILE_SIZE;
         }
     }
 }
 
-fn move_distance_marker(mut query: Query<&mut DistanceTracker>) {
+/// Floating origin: once the player is far from the origin, moves every root
+/// entity back by the same amount. Children like signs follow their gates, and
+/// UI nodes are skipped since their transforms come from the layout.
+#[allow(clippy::type_complexity)]
+fn recenter_world(
+    mut root_query: Query<
+        (&mut Transform, Option<&DistanceTracker>),
+        (Without<Parent>, Without<Node>),
+    >,
+) {
+    let Some((player_x, distance_traveled)) = root_query.iter().find_map(|(transform, tracker)| {
+        Some((transform.translation.x, tracker?.distance_traveled))
+    }) else {
+        return;
+    };
+    if player_x < RECENTER_DISTANCE {
+        return;
+    }
+
+    // shift by whole chessboard periods so the ground rows keep their grid positions
+    let period = 2. * GROUND_TILE_SIZE;
+    let shift = (player_x / period).floor() * period;
+    for (mut transform, _) in &mut root_query {
+        transform.translation.x -= shift;
+    }
+    debug!(
+        "re-centered the world by {} at a distance of {:.1}",
+        shift, distance_traveled
+    );
+}
+
+fn move_distance_marker(mut query: Query<&mut DistanceTracker>, gameplay: Res<RunGameplay>) {
     for mut distnace_tracker in &mut query {
-        distnace_tracker.distance_traveled += ADVANCE_AMOUNT_PER_STEP;
+        distnace_tracker.distance_traveled += gameplay.speed as f64;
     }
 }
 
 #[allow(clippy::too_many_arguments)]
 fn sign_spawn_manager(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut images: ResMut<Assets<Image>>,
-    query: Query<&DistanceTracker>,
-    // signs_query: Query<(Entity, &Transform, &Sign)>,
+    player_query: Query<&Transform, (With<DistanceTracker>, Without<Gate>)>,
     signs_query: Query<&Sign>,
-    gate_query: Query<(Entity, &Transform, &Children), With<Gate>>,
-    vocabulary: Res<WordList>,
-    mut asset_server: Res<AssetServer>,
+    mut gate_query: Query<(&mut Transform, &mut Gate, &Children)>,
+    quiz: Res<QuizState>,
+    mut rng: ResMut<GateRng>,
+    session: Res<StudySession>,
+    ui_font: Res<UiFont>,
+    gameplay: Res<RunGameplay>,
+    time: Res<Time>,
 ) {
-    let distance_traveled = query.single().distance_traveled;
-
-    // Despawn Gate
-    for (entity, gate_transform, children) in &gate_query {
-        if distance_traveled - gate_transform.translation.x > 10. {
-            for child in children {
-                if let Ok(sign) = signs_query.get(*child) {
-                    images.remove(sign.image_handle.id());
-                    commands.entity(sign.ui_id).despawn_recursive();
-                }
+    let player_x = player_query.single().translation.x;
+    let mut front_x = gate_query
+        .iter()
+        .map(|(transform, ..)| transform.translation.x)
+        .fold(player_x, f32::max);
+
+    // Recycle gates that fell behind by moving them to the front with a new card,
+    // so their signs keep their render targets
+    for (mut gate_transform, mut gate, children) in &mut gate_query {
+        if player_x - gate_transform.translation.x > 10. {
+            let prompt = quiz.next_prompt(&mut rng.0);
+            let furigana = quiz.furigana(prompt.card, session.study.furigana);
+
+            for sign in signs_query.iter_many(children) {
+                write_sign(
+                    &mut commands,
+                    &ui_font,
+                    sign,
+                    quiz.card(prompt.card),
+                    &prompt,
+                    furigana,
+                );
             }
-            commands.entity(entity).despawn_recursive();
-            let (main_translation, off_translation) = vocabulary.get_weighted_word_pair();
-            let spawn_distance =
-                distance_traveled + SIGN_SPACING_DISTANCE * (NUMBER_OF_SIGNS - 1) as f32;
-            spawn_gate(
-                &mut commands,
-                &mut meshes,
-                &mut materials,
-                &mut images,
-                main_translation,
-                off_translation,
-                spawn_distance,
-                &mut asset_server,
-            );
+
+            if let Some(material) = materials.get_mut(&gate.material_handle) {
+                material.base_color = GATE_COLOR;
+            }
+            front_x += gameplay.gate_spacing;
+            gate_transform.translation.x = front_x;
+            gate.prompt = prompt;
+            gate.gate_state = GateState::Unpass;
+            gate.shown_at = time.elapsed();
         }
     }
 }
 
+/// Spawns a sign with its own render target, or with a glyph mesh when the
+/// glyph atlas backend is in use. The text is filled in by `write_sign` and
+/// replaced every time the sign's gate is recycled.
 #[allow(clippy::too_many_arguments)]
 fn create_sign(
     commands: &mut Commands,
+    meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     images: &mut ResMut<Assets<Image>>,
-    text_content: &str,
+    glyph_atlas: Option<&GlyphAtlas>,
+    slot: SignSlot,
     transform: Transform,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    asset_server: &mut Res<AssetServer>,
+    sign_mesh_handle: Handle<Mesh>,
     gate_id: &Entity,
-) -> Entity {
+) -> Sign {
+    if let Some(atlas) = glyph_atlas {
+        let text_id = commands
+            .spawn((
+                Mesh3d(meshes.add(Rectangle::new(0., 0.))),
+                MeshMaterial3d(atlas.text_material.clone()),
+                glyph_text_transform(),
+                NotShadowCaster,
+                Name::new("sign text"),
+            ))
+            .id();
+        let sign = Sign {
+            target: SignTarget::Glyphs(text_id),
+            slot,
+        };
+        let sign_mesh = commands
+            .spawn((
+                Mesh3d(sign_mesh_handle),
+                MeshMaterial3d(atlas.board_material.clone()),
+                transform,
+                Name::new("sign"),
+                sign.clone(),
+            ))
+            .add_child(text_id)
+            .id();
+        commands.entity(*gate_id).add_child(sign_mesh);
+        return sign;
+    }
+
     let size = Extent3d {
         width: 512,
         height: 512,
@@ -525,9 +795,7 @@ fn create_sign(
         ))
         .id();
 
-    // Set up the UI text for the texture
-    // let font = asset_server.load("MesloLGS NF Regular.ttf");
-    let font = asset_server.load("NotoSansJP-Regular.ttf");
+    // Set up the UI root for the texture, its text is spawned by `write_sign`
     let ui = commands
         .spawn((
             Node {
@@ -541,17 +809,6 @@ fn create_sign(
             BackgroundColor(GOLD.into()),
             TargetCamera(texture_camera),
         ))
-        .with_children(|parent| {
-            parent.spawn((
-                Text::new(text_content),
-                TextFont {
-                    font,
-                    font_size: 100.0,
-                    ..default()
-                },
-                TextColor::BLACK,
-            ));
-        })
         .id();
 
     // This material has the texture that has been rendered.
@@ -562,10 +819,15 @@ fn create_sign(
         ..default()
     });
 
+    let sign = Sign {
+        target: SignTarget::Ui(ui),
+        slot,
+    };
+
     // commands.entity(parent_entity).add_child(ui);
     let sign_mesh = commands
         .spawn((
-            Mesh3d(meshes.add(Cuboid::new(1.0, 4.0, 2.5))),
+            Mesh3d(sign_mesh_handle),
             // material: materials.add(Color::srgb_u8(124, 144, 255)),
             MeshMaterial3d(material_handle),
             transform,
@@ -573,24 +835,131 @@ fn create_sign(
         ))
         .id();
 
-    // commands.entity(sign_mesh).add_child(ui);
-    commands.entity(sign_mesh).insert(Sign {
-        image_handle: image_handle.clone(),
-        ui_id: ui,
-    });
+    // The ui can't be a child of sign_mesh: bevy_ui only lays out nodes without a parent
+    // as roots, so a node under a mesh is never laid out and the sign renders black.
+    // Instead `Sign` despawns it from its on_remove hook, so it goes together with the
+    // camera child and the image, whose last handles live in the sign's material.
+    commands.entity(sign_mesh).insert(sign.clone());
     commands.entity(sign_mesh).add_child(texture_camera);
-    // commands.entity(sign_mesh).add_child(ui);
-
-    // todo I don't know why but if I add ui as a child of sign_mesh, the signs are black.... so
-    // for a work around I am storing the id in the Sign component and despawning it manually.
-    // commands
-    //     .entity(sign_mesh)
-    //     .push_children(&[texture_camera, ui]);
 
     commands.entity(*gate_id).add_child(sign_mesh);
     // commands.entity(*gate_id).add_child(sign_mesh);
 
-    sign_mesh
+    sign
+}
+
+/// Replaces the text rendered by `sign` with its part of a gate's prompt: the
+/// card on the prompt sign and one of the choices on each lane sign.
+fn write_sign(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    sign: &Sign,
+    card: &Card,
+    prompt: &Prompt,
+    furigana: Option<&str>,
+) {
+    let (text_content, label, furigana) = match sign.slot {
+        SignSlot::Prompt => (
+            card.word.as_str(),
+            card.category.map(|category| category.label()),
+            furigana,
+        ),
+        SignSlot::Left => (
+            prompt.choices[CorrectSide::Left.choice()].as_str(),
+            None,
+            None,
+        ),
+        SignSlot::Right => (
+            prompt.choices[CorrectSide::Right.choice()].as_str(),
+            None,
+            None,
+        ),
+    };
+
+    let ui_id = match sign.target {
+        SignTarget::Ui(ui_id) => ui_id,
+        SignTarget::Glyphs(text_id) => {
+            let segments = match furigana {
+                Some(reading) => ruby_segments(text_content, reading),
+                None => vec![RubySegment {
+                    base: text_content.to_owned(),
+                    ruby: None,
+                }],
+            };
+            commands
+                .entity(text_id)
+                .insert(GlyphText::new(label, segments));
+            return;
+        }
+    };
+
+    commands
+        .entity(ui_id)
+        .despawn_descendants()
+        .with_children(|parent| {
+            if let Some(label) = label {
+                parent.spawn((
+                    Text::new(label),
+                    TextFont {
+                        font: font.clone(),
+                        font_size: 40.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.3, 0.3, 0.3)),
+                ));
+            }
+            match furigana {
+                // ruby text: each kanji run gets its reading in a smaller font above it
+                Some(reading) => {
+                    parent
+                        .spawn(Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::FlexEnd,
+                            ..default()
+                        })
+                        .with_children(|row| {
+                            for segment in ruby_segments(text_content, reading) {
+                                row.spawn(Node {
+                                    flex_direction: FlexDirection::Column,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                })
+                                .with_children(|column| {
+                                    column.spawn((
+                                        Text::new(segment.ruby.unwrap_or_default()),
+                                        TextFont {
+                                            font: font.clone(),
+                                            font_size: 40.0,
+                                            ..default()
+                                        },
+                                        TextColor::BLACK,
+                                    ));
+                                    column.spawn((
+                                        Text::new(segment.base),
+                                        TextFont {
+                                            font: font.clone(),
+                                            font_size: 100.0,
+                                            ..default()
+                                        },
+                                        TextColor::BLACK,
+                                    ));
+                                });
+                            }
+                        });
+                }
+                None => {
+                    parent.spawn((
+                        Text::new(text_content),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 100.0,
+                            ..default()
+                        },
+                        TextColor::BLACK,
+                    ));
+                }
+            }
+        });
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -599,148 +968,97 @@ fn spawn_gate(
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
     images: &mut ResMut<Assets<Image>>,
-    word: &Word,
-    other_word: &Word,
+    quiz: &Quiz,
+    prompt: Prompt,
+    furigana: FuriganaMode,
     distance: f32,
-    asset_server: &mut Res<AssetServer>,
+    shown_at: Duration,
+    font: &Handle<Font>,
+    glyph_atlas: Option<&GlyphAtlas>,
 ) {
     const SIGN_DISTANCE_FROM_CENTER: f32 = 4.;
     let sign_distance_from_gate = 3.;
 
-    let mut rng = rand::thread_rng();
-    let correct_side = if rng.gen_bool(0.5) {
-        // 50% chance for each side
-        CorrectSide::Left
-    } else {
-        CorrectSide::Right
-    };
+    let card = quiz.card(prompt.card);
+    let furigana = quiz.furigana(prompt.card, furigana);
 
-    let (left_translation, right_translation) = match correct_side {
-        CorrectSide::Left => (word, other_word),
-        CorrectSide::Right => (other_word, word),
-    };
-
-    let gate_material_handle = materials.add(Color::srgb_u8(50, 50, 50));
+    let gate_material_handle = materials.add(GATE_COLOR);
     let gate_id = commands
         .spawn((
             Mesh3d(meshes.add(Cylinder::new(0.2, 2.5))),
             MeshMaterial3d(gate_material_handle.clone()),
             Transform::from_xyz(distance, -0.5, 0.0),
             Gate {
-                word: word.to_owned(),
+                prompt: prompt.clone(),
                 gate_state: GateState::Unpass,
-                correct_side,
                 material_handle: gate_material_handle,
+                shown_at,
             },
         ))
         .id();
 
-    #[allow(unused_variables)]
-    let (left_text, right_text) = (
-        left_translation.translation.as_str(),
-        right_translation.translation.as_str(),
-    );
+    let sign_mesh_handle = meshes.add(Cuboid::new(1.0, 4.0, 2.5));
 
-    let transform = Transform::from_xyz(sign_distance_from_gate, 1.5, -SIGN_DISTANCE_FROM_CENTER)
-        .with_rotation(Quat::from_rotation_x(-PI / 2.) * Quat::from_rotation_z(PI / 16.));
-
-    create_sign(
-        commands,
-        materials,
-        images,
-        left_text,
-        transform,
-        meshes,
-        asset_server,
-        &gate_id,
-    );
+    let left_transform =
+        Transform::from_xyz(sign_distance_from_gate, 1.5, -SIGN_DISTANCE_FROM_CENTER)
+            .with_rotation(Quat::from_rotation_x(-PI / 2.) * Quat::from_rotation_z(PI / 16.));
 
     // Middle sign
-    let transform = Transform::from_xyz(sign_distance_from_gate, 4.5, 0.0)
+    let prompt_transform = Transform::from_xyz(sign_distance_from_gate, 4.5, 0.0)
         .with_rotation(Quat::from_rotation_x(-PI / 2.) * Quat::from_rotation_y(-PI / 16.));
 
-    create_sign(
-        commands,
-        materials,
-        images,
-        word.word.as_str(),
-        transform,
-        meshes,
-        asset_server,
-        &gate_id,
-    );
-
     // Right sign
-    #[allow(unused_variables)]
-    let transform = Transform::from_xyz(sign_distance_from_gate, 1.5, SIGN_DISTANCE_FROM_CENTER)
-        .with_rotation(Quat::from_rotation_x(-PI / 2.) * Quat::from_rotation_z(-PI / 16.));
-
-    create_sign(
-        commands,
-        materials,
-        images,
-        right_text,
-        transform,
-        meshes,
-        asset_server,
-        &gate_id,
-    );
+    let right_transform =
+        Transform::from_xyz(sign_distance_from_gate, 1.5, SIGN_DISTANCE_FROM_CENTER)
+            .with_rotation(Quat::from_rotation_x(-PI / 2.) * Quat::from_rotation_z(-PI / 16.));
+
+    for (slot, transform) in [
+        (SignSlot::Left, left_transform),
+        (SignSlot::Prompt, prompt_transform),
+        (SignSlot::Right, right_transform),
+    ] {
+        let sign = create_sign(
+            commands,
+            meshes,
+            materials,
+            images,
+            glyph_atlas,
+            slot,
+            transform,
+            sign_mesh_handle.clone(),
+            &gate_id,
+        );
+        write_sign(commands, font, &sign, card, &prompt, furigana);
+    }
 }
 
 fn gate_pass_checker(
-    mut query: Query<(&Transform, &mut Gate)>,
-    single_query: Query<&DistanceTracker>,
-    player_query: Query<&Transform, With<Person>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
-    mut ui_interface: ResMut<UiInterface>,
-    mut vocabulary: ResMut<WordList>,
+    mut query: Query<(Entity, &Transform, &mut Gate)>,
+    player_query: Query<&Transform, (With<DistanceTracker>, Without<Gate>)>,
+    time: Res<Time>,
+    mut gate_passed: EventWriter<GatePassed>,
 ) {
-    fn find_index(word: &Word, words: &[Word]) -> usize {
-        words
-            .iter()
-            .position(|x| x == word)
-            .expect("could not match word")
-    }
-    for (transform, mut gate) in &mut query {
+    // the camera is a `Person` too, only the cube answers gates
+    let player_trastform = player_query.single();
+    for (entity, transform, mut gate) in &mut query {
         match gate.gate_state {
             GateState::Passed => {}
             GateState::Unpass => {
-                let distance_traveled = single_query.single().distance_traveled;
-                let player_trastform = player_query.iter().last().unwrap();
-                if distance_traveled >= transform.translation.x {
+                if player_trastform.translation.x >= transform.translation.x {
                     let player_side = if player_trastform.translation.z > 0. {
                         CorrectSide::Right
                     } else {
                         CorrectSide::Left
                     };
 
-                    if gate.correct_side == player_side {
-                        ui_interface.text_output = format!(
-                            "Yes: \"{}\" => \"{}\"",
-                            gate.word.word, gate.word.translation
-                        );
-
-                        ui_interface.streak += 1;
-                        let index = find_index(&gate.word, &vocabulary.words);
-                        vocabulary.weights[index] = (vocabulary.weights[index] * 0.5).max(1.);
-
-                        if let Some(material) = materials.get_mut(&gate.material_handle) {
-                            material.base_color = Color::srgb(0.2, 0.8, 0.2);
-                        }
-                    } else {
-                        ui_interface.text_output = format!(
-                            "No: \"{}\" => \"{}\"",
-                            gate.word.word, gate.word.translation
-                        );
-
-                        ui_interface.streak = 0;
-                        let index = find_index(&gate.word, &vocabulary.words);
-                        vocabulary.weights[index] += 50.;
-
-                        if let Some(material) = materials.get_mut(&gate.material_handle) {
-                            material.base_color = Color::srgb(0.8, 0.2, 0.2);
-                        }
-                    }
+                    gate_passed.send(GatePassed {
+                        gate: entity,
+                        prompt: gate.prompt.clone(),
+                        chosen_lane: player_side,
+                        correct: player_side.choice() == gate.prompt.correct_index,
+                        latency: time.elapsed().saturating_sub(gate.shown_at),
+                        z: player_trastform.translation.z,
+                    });
                     gate.gate_state = GateState::Passed;
                 }
             }
@@ -748,56 +1066,242 @@ fn gate_pass_checker(
     }
 }
 
+/// Submits passed gates to the quiz, which reweighs their cards and keeps the streak.
+fn score_gate(mut events: EventReader<GatePassed>, mut quiz: ResMut<QuizState>) {
+    for event in events.read() {
+        quiz.submit(&event.prompt, event.chosen_lane.choice());
+    }
+}
+
+fn color_gate(
+    mut events: EventReader<GatePassed>,
+    gate_query: Query<&Gate>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for event in events.read() {
+        // a gate gone by now has nothing left to colour
+        let Ok(gate) = gate_query.get(event.gate) else {
+            continue;
+        };
+        if let Some(material) = materials.get_mut(&gate.material_handle) {
+            material.base_color = if event.correct {
+                Color::srgb(0.2, 0.8, 0.2)
+            } else {
+                Color::srgb(0.8, 0.2, 0.2)
+            };
+        }
+    }
+}
+
+/// Shows the answer and the streak on the HUD.
+fn show_gate_feedback(
+    mut events: EventReader<GatePassed>,
+    mut ui_interface: ResMut<UiInterface>,
+    quiz: Res<QuizState>,
+    kanji_index: Res<KanjiInfo>,
+) {
+    for event in events.read() {
+        let card = quiz.card(event.prompt.card);
+        ui_interface.text_output = format!(
+            "{}: \"{}\" => \"{}\"{}",
+            if event.correct { "Yes" } else { "No" },
+            card.word,
+            card.translation,
+            kanji_index.describe(&card.word)
+        );
+        ui_interface.streak = quiz.streak();
+    }
+}
+
+fn record_gate(
+    mut events: EventReader<GatePassed>,
+    quiz: Res<QuizState>,
+    mut replay: ResMut<Replay>,
+) {
+    for event in events.read() {
+        replay.gate_passed(
+            &event.prompt,
+            &quiz.card(event.prompt.card).word,
+            event.chosen_lane.choice(),
+            event.z,
+        );
+    }
+}
+
+fn log_gate(mut events: EventReader<GatePassed>, quiz: Res<QuizState>) {
+    for event in events.read() {
+        debug!(
+            "passed \"{}\" on the {:?} lane, {} after {:.2}s",
+            quiz.card(event.prompt.card).word,
+            event.chosen_lane,
+            if event.correct { "right" } else { "wrong" },
+            event.latency.as_secs_f32()
+        );
+    }
+}
+
+/// Hands the gameplay settings to the run on a fixed tick, so a replay changes
+/// them on the same tick. A replay or ghost race follows its recording instead
+/// of the menu.
+fn take_gameplay_settings(
+    settings: Res<Settings>,
+    mut gameplay: ResMut<RunGameplay>,
+    mut replay: ResMut<Replay>,
+    best_run: Option<Res<BestRun>>,
+) {
+    let followed = replay
+        .played_back()
+        .or(best_run.as_ref().and_then(|best| best.recording()));
+    let next = match followed {
+        Some(recording) => {
+            if settings.is_changed() && settings.gameplay != gameplay.0 {
+                info!("this run plays with the gameplay settings of its recording, changes apply from the next run");
+            }
+            match recording.gameplay_change(replay.tick()) {
+                Some(next) => next,
+                None => return,
+            }
+        }
+        None => settings.gameplay,
+    };
+    if next != gameplay.0 {
+        gameplay.0 = next;
+        replay.gameplay_changed(next);
+    }
+}
+
+/// Applies gameplay settings that are not read every tick.
+fn apply_gameplay_settings(gameplay: Res<RunGameplay>, mut quiz: ResMut<QuizState>) {
+    // new rules are not progress, so the progress file is left alone
+    quiz.bypass_change_detection()
+        .set_rules(gameplay.review_rules());
+}
+
+fn show_fps_overlay(settings: Res<Settings>, mut fps_overlay: ResMut<FpsOverlayConfig>) {
+    fps_overlay.enabled = settings.gameplay.fps_overlay;
+}
+
+/// Adds gates at the front, or takes the frontmost ones away, when the number
+/// of gates is changed in the settings.
+#[allow(clippy::too_many_arguments)]
+fn sync_gate_count(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+    gate_query: Query<(Entity, &Transform), With<Gate>>,
+    player_query: Query<&Transform, With<DistanceTracker>>,
+    quiz: Res<QuizState>,
+    mut rng: ResMut<GateRng>,
+    session: Res<StudySession>,
+    gameplay: Res<RunGameplay>,
+    fixed_time: Res<Time<Fixed>>,
+    ui_font: Res<UiFont>,
+    glyph_atlas: Option<Res<GlyphAtlas>>,
+) {
+    let mut gates: Vec<(Entity, f32)> = gate_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.x))
+        .collect();
+    gates.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let wanted = gameplay.gates as usize;
+
+    for (entity, _) in gates.iter().skip(wanted) {
+        commands.entity(*entity).despawn_recursive();
+    }
+    let mut front_x = gates
+        .last()
+        .map_or(player_query.single().translation.x, |gate| gate.1);
+    for _ in gates.len()..wanted {
+        front_x += gameplay.gate_spacing;
+        let prompt = quiz.next_prompt(&mut rng.0);
+        spawn_gate(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            &mut images,
+            &quiz,
+            prompt,
+            session.study.furigana,
+            front_x,
+            fixed_time.elapsed(),
+            &ui_font,
+            glyph_atlas.as_deref(),
+        );
+    }
+}
+
+/// Adds the run's answers to the progress file shared with the terminal
+/// frontend, on top of what it holds, since a seeded run or ghost race draws
+/// its gates from other weights.
+fn save_progress(mut quiz: ResMut<QuizState>, replay: Res<Replay>) {
+    // a played back run must not count as reviews
+    if replay.is_playing() {
+        return;
+    }
+    // taking the saved answers out is not a change to save again
+    if let Err(err) = quiz.bypass_change_detection().save_progress(PROGRESS_FILE) {
+        warn!("{}", err);
+    }
+}
+
 pub fn close_on_esc(
     mut commands: Commands,
     focused_windows: Query<(Entity, &Window)>,
-    input: Res<ButtonInput<KeyCode>>,
+    actions: Res<ButtonInput<Action>>,
 ) {
     for (window, focus) in focused_windows.iter() {
         if !focus.focused {
             continue;
         }
 
-        if input.just_pressed(KeyCode::Escape) {
+        if actions.just_pressed(Action::Quit) {
             commands.entity(window).despawn();
         }
     }
 }
 
-fn read_full_translation_file(file_name: &str) -> Vec<JlptWord> {
-    #[derive(Debug, Deserialize, Resource)]
-    struct JlptTable {
-        words: Vec<JlptWord>,
+/// Freezes the run, `FixedUpdate` does not tick while virtual time is paused.
+fn toggle_pause(actions: Res<ButtonInput<Action>>, mut time: ResMut<Time<Virtual>>) {
+    if actions.just_pressed(Action::Pause) {
+        if time.is_paused() {
+            time.unpause();
+        } else {
+            time.pause();
+        }
     }
-
-    // Read the TOML file
-    let content = fs::read_to_string(file_name).expect("could not read translation file");
-
-    // Parse the TOML content
-    let temp_table: JlptTable = toml::from_str(&content).expect("could not parse vocab file");
-    temp_table.words
-}
-
-fn read_translation_file(file_name: &str) -> Vocabulary {
-    // Read the TOML file
-    let content = fs::read_to_string(file_name).expect("could not read translation file");
-
-    // Parse the TOML content
-    toml::from_str(&content).expect("could not parse vocab file")
 }
 
-fn read_hiragana_file(file_name: &str) -> Vec<Hiragana> {
-    #[allow(dead_code)]
-    #[derive(Debug, Deserialize, Resource)]
-    struct HiraganaList {
-        hiragana: Vec<Hiragana>,
+/// Shows furigana on the prompt signs of the gates ahead, whatever the furigana mode.
+fn reveal_furigana(
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    gate_query: Query<(&Gate, &Children)>,
+    signs_query: Query<&Sign>,
+    quiz: Res<QuizState>,
+    ui_font: Res<UiFont>,
+) {
+    if !actions.just_pressed(Action::RevealFurigana) {
+        return;
+    }
+    for (gate, children) in &gate_query {
+        let card = quiz.card(gate.prompt.card);
+        if matches!(gate.gate_state, GateState::Passed) || card.reading.is_none() {
+            continue;
+        }
+        for sign in signs_query.iter_many(children) {
+            if sign.slot == SignSlot::Prompt {
+                write_sign(
+                    &mut commands,
+                    &ui_font,
+                    sign,
+                    card,
+                    &gate.prompt,
+                    card.reading.as_deref(),
+                );
+            }
+        }
     }
-    // Read the TOML file
-    let content = fs::read_to_string(file_name).expect("could not read translation file");
-
-    // Parse the TOML content
-    let temp_table: HiraganaList = toml::from_str(&content).expect("could not parse vocab file");
-    temp_table.hiragana
 }
 
 #[allow(dead_code)]
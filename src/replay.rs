@@ -0,0 +1,309 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use flashcard_core::{Progress, Prompt, Quiz};
+
+use crate::settings::Gameplay;
+
+/// Everything needed to play a run again: how it was set up and where the
+/// player was on every fixed tick. With the same seed and starting progress
+/// the gates come up the same, so the lane positions replay the whole run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// Command line of the run, without `--record`.
+    pub args: Vec<String>,
+    pub seed: u64,
+    /// Player z on every fixed tick, run-length encoded as `[ticks, z]`.
+    lane: Vec<(u32, f32)>,
+    /// Progress of the run's cards when it started, so cards are drawn with the same weights.
+    progress: Progress,
+    /// Every gate in the order it was passed.
+    gates: Vec<GateRecord>,
+    /// Gameplay settings the run started with.
+    #[serde(default)]
+    gameplay: Gameplay,
+    /// Gameplay settings changed in the menu during the run.
+    #[serde(default)]
+    gameplay_changes: Vec<GameplayChange>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+        toml::from_str(&content)
+            .map_err(|err| format!("could not parse {}: {}", path.display(), err))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, content)
+            .map_err(|err| format!("could not write {}: {}", path.display(), err))
+    }
+
+    /// The player z of every tick.
+    pub fn lane_positions(&self) -> Vec<f32> {
+        self.lane
+            .iter()
+            .flat_map(|(ticks, z)| std::iter::repeat_n(*z, *ticks as usize))
+            .collect()
+    }
+
+    /// Whether each gate was answered right, in the order they were passed.
+    pub fn gate_results(&self) -> Vec<bool> {
+        self.gates
+            .iter()
+            .map(|gate| gate.chosen == gate.correct_index)
+            .collect()
+    }
+
+    /// Progress of the run's cards when it started.
+    pub fn progress(&self) -> &Progress {
+        &self.progress
+    }
+
+    /// The gameplay settings the run started with.
+    pub fn gameplay(&self) -> Gameplay {
+        self.gameplay
+    }
+
+    /// The gameplay settings the run changed to on `tick`, if it did.
+    pub fn gameplay_change(&self, tick: u32) -> Option<Gameplay> {
+        self.gameplay_changes
+            .iter()
+            .rev()
+            .find(|change| change.tick == tick)
+            .map(|change| change.gameplay)
+    }
+
+    pub fn correct_answers(&self) -> usize {
+        self.gates
+            .iter()
+            .filter(|gate| gate.chosen == gate.correct_index)
+            .count()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct GateRecord {
+    tick: u32,
+    word: String,
+    choices: Vec<String>,
+    correct_index: usize,
+    chosen: usize,
+    /// Player z the answer was read from.
+    z: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GameplayChange {
+    tick: u32,
+    gameplay: Gameplay,
+}
+
+/// Whether the current run is being recorded or played back (`--replay`).
+/// Runs are recorded in memory unless played back, and written out when
+/// `--record` names a file.
+#[derive(Resource, Default)]
+pub enum Replay {
+    #[default]
+    Off,
+    Recording {
+        path: Option<String>,
+        recording: Recording,
+        tick: u32,
+    },
+    Playing {
+        recording: Recording,
+        /// `recording.lane` expanded to one position per tick.
+        lane: Vec<f32>,
+        tick: u32,
+        gates: usize,
+    },
+}
+
+impl Replay {
+    /// Starts recording, to `path` if given, keeping the other arguments to set
+    /// up the replay with.
+    pub fn record(path: Option<String>, args: &[String]) -> Self {
+        let mut kept = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if arg == "--record" {
+                args.next();
+            } else {
+                kept.push(arg.clone());
+            }
+        }
+        Replay::Recording {
+            path,
+            recording: Recording {
+                args: kept,
+                ..default()
+            },
+            tick: 0,
+        }
+    }
+
+    pub fn play(path: &str) -> Result<Self, String> {
+        let recording = Recording::load(Path::new(path))?;
+        let lane = recording.lane_positions();
+        Ok(Replay::Playing {
+            recording,
+            lane,
+            tick: 0,
+            gates: 0,
+        })
+    }
+
+    pub fn recording(&self) -> Option<&Recording> {
+        match self {
+            Replay::Off => None,
+            Replay::Recording { recording, .. } | Replay::Playing { recording, .. } => {
+                Some(recording)
+            }
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Replay::Playing { .. })
+    }
+
+    /// The run being played back.
+    pub fn played_back(&self) -> Option<&Recording> {
+        match self {
+            Replay::Playing { recording, .. } => Some(recording),
+            _ => None,
+        }
+    }
+
+    /// Notes the seed, the starting progress and the gameplay settings of a
+    /// recorded run.
+    pub fn begin(&mut self, seed: u64, quiz: &Quiz, gameplay: Gameplay) {
+        if let Replay::Recording { recording, .. } = self {
+            recording.seed = seed;
+            quiz.record(&mut recording.progress);
+            recording.gameplay = gameplay;
+        }
+    }
+
+    /// The fixed tick the run is on.
+    pub fn tick(&self) -> u32 {
+        match self {
+            Replay::Off => 0,
+            Replay::Recording { tick, .. } | Replay::Playing { tick, .. } => *tick,
+        }
+    }
+
+    /// Records the gameplay settings changing on the current tick.
+    pub fn gameplay_changed(&mut self, gameplay: Gameplay) {
+        if let Replay::Recording {
+            recording, tick, ..
+        } = self
+        {
+            recording.gameplay_changes.push(GameplayChange {
+                tick: *tick,
+                gameplay,
+            });
+        }
+    }
+
+    /// The recorded player z for the current tick, `None` when not playing back
+    /// or once the recording ran out.
+    pub fn lane_position(&self) -> Option<f32> {
+        match self {
+            Replay::Playing { lane, tick, .. } => lane.get(*tick as usize).copied(),
+            _ => None,
+        }
+    }
+
+    /// Ends a fixed tick with the player at `z`.
+    pub fn end_tick(&mut self, z: f32) {
+        match self {
+            Replay::Off => {}
+            Replay::Recording {
+                recording, tick, ..
+            } => {
+                match recording.lane.last_mut() {
+                    Some((ticks, last)) if *last == z => *ticks += 1,
+                    _ => recording.lane.push((1, z)),
+                }
+                *tick += 1;
+            }
+            Replay::Playing { lane, tick, .. } => {
+                *tick += 1;
+                if *tick as usize == lane.len() {
+                    info!("replay finished, the player has control again");
+                }
+            }
+        }
+    }
+
+    /// Records a gate answered with `chosen`, or checks a played back gate
+    /// against the recording.
+    pub fn gate_passed(&mut self, prompt: &Prompt, word: &str, chosen: usize, z: f32) {
+        let gate = |tick: u32| GateRecord {
+            tick,
+            word: word.to_owned(),
+            choices: prompt.choices.clone(),
+            correct_index: prompt.correct_index,
+            chosen,
+            z,
+        };
+        match self {
+            Replay::Off => {}
+            Replay::Recording {
+                recording, tick, ..
+            } => recording.gates.push(gate(*tick)),
+            Replay::Playing {
+                recording,
+                tick,
+                gates,
+                ..
+            } => {
+                let replayed = gate(*tick);
+                let recorded = recording.gates.get(*gates);
+                if recorded != Some(&replayed) {
+                    warn!(
+                        "replay diverged at gate {}: recorded {:?}, replayed {:?}",
+                        gates, recorded, replayed
+                    );
+                }
+                *gates += 1;
+            }
+        }
+    }
+
+    fn save(&self) {
+        let Replay::Recording {
+            path: Some(path),
+            recording,
+            ..
+        } = self
+        else {
+            return;
+        };
+        match recording.save(Path::new(path)) {
+            Ok(()) => info!("recorded the run to {}", path),
+            Err(err) => warn!("{}", err),
+        }
+    }
+}
+
+/// Writes a recorded run to its file when the game closes.
+pub struct Replays;
+
+impl Plugin for Replays {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Replay>()
+            .add_systems(Last, save_recording);
+    }
+}
+
+fn save_recording(mut exits: EventReader<AppExit>, replay: Res<Replay>) {
+    if exits.read().next().is_some() {
+        replay.save();
+    }
+}
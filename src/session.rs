@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use flashcard_core::StudyOptions;
+
+/// How the text on signs is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignBackend {
+    /// A UI tree per sign, rendered into the sign's texture by its own camera.
+    RenderTarget,
+    /// Quads in the 3D scene sampling one shared glyph atlas, no extra cameras.
+    GlyphAtlas,
+}
+
+impl std::str::FromStr for SignBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "render-target" => Ok(SignBackend::RenderTarget),
+            "glyph-atlas" => Ok(SignBackend::GlyphAtlas),
+            _ => Err(format!(
+                "unknown sign backend \"{}\" (expected render-target or glyph-atlas)",
+                s
+            )),
+        }
+    }
+}
+
+/// How the player steers between the lanes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlScheme {
+    /// The mouse or touch x position maps straight to the player position.
+    #[default]
+    Analog,
+    /// Arrow keys, A/D, a gamepad or touch snap the player to the left or right lane.
+    Lanes,
+}
+
+impl ControlScheme {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ControlScheme::Analog => "analog",
+            ControlScheme::Lanes => "lanes",
+        }
+    }
+}
+
+impl std::str::FromStr for ControlScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "analog" | "mouse" => Ok(ControlScheme::Analog),
+            "lanes" => Ok(ControlScheme::Lanes),
+            _ => Err(format!(
+                "unknown control scheme \"{}\" (expected analog or lanes)",
+                s
+            )),
+        }
+    }
+}
+
+/// What the current run studies and how, picked on the command line.
+///
+/// ```text
+/// flash_card_proto --deck jlpt --jlpt N3          # N3 only
+/// flash_card_proto --deck jlpt --jlpt N5..N4      # N4 and below
+/// flash_card_proto --deck hiragana,vocab
+/// flash_card_proto --deck vocab,jlpt --jlpt N5 --category verb
+/// flash_card_proto --mode conjugation
+/// flash_card_proto --mode kanji-reading --jlpt N5
+/// flash_card_proto --deck vocab --furigana never
+/// flash_card_proto --signs glyph-atlas
+/// flash_card_proto --control lanes
+/// flash_card_proto --seed 1234
+/// flash_card_proto --record run.toml
+/// flash_card_proto --replay run.toml
+/// ```
+#[derive(Debug, Clone, Resource)]
+pub struct StudySession {
+    /// Decks, filters and mode, parsed the same way as in the terminal frontend.
+    pub study: StudyOptions,
+    pub sign_backend: SignBackend,
+    /// Overrides the control scheme in the settings file.
+    pub control: Option<ControlScheme>,
+    /// Seeds the gate sequence, overriding the seed in the settings file.
+    pub seed: Option<u64>,
+    /// File the run is recorded to.
+    pub record: Option<String>,
+    /// Recorded run to play back, which also sets up the decks, mode and seed.
+    pub replay: Option<String>,
+}
+
+impl Default for StudySession {
+    fn default() -> Self {
+        StudySession {
+            study: StudyOptions::default(),
+            sign_backend: SignBackend::RenderTarget,
+            control: None,
+            seed: None,
+            record: None,
+            replay: None,
+        }
+    }
+}
+
+impl StudySession {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut session = StudySession::default();
+        session.study = StudyOptions::from_args(args, |arg, value| {
+            match arg {
+                "--signs" => session.sign_backend = value()?.parse()?,
+                "--control" => session.control = Some(value()?.parse()?),
+                "--seed" => {
+                    let seed = value()?;
+                    session.seed = Some(
+                        seed.parse()
+                            .map_err(|_| format!("invalid seed \"{}\"", seed))?,
+                    );
+                }
+                "--record" => session.record = Some(value()?),
+                "--replay" => session.replay = Some(value()?),
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(session)
+    }
+}
@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::ops::RangeInclusive;
+
+use flashcard_core::ReviewRules;
+
+use crate::fonts::FontChain;
+use crate::input::InputMap;
+use crate::session::ControlScheme;
+
+const SETTINGS_FILE: &str = "settings.toml";
+
+/// Player preferences kept between runs in `settings.toml`, next to the game.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct Settings {
+    /// Seeds every run with the same gate sequence, a random seed is picked
+    /// and logged when unset.
+    pub seed: Option<u64>,
+    /// How the player steers, `--control` overrides it.
+    pub control: ControlScheme,
+    pub fonts: FontChain,
+    pub gameplay: Gameplay,
+    pub input: InputMap,
+}
+
+/// The gameplay settings the current run plays with. Menu changes reach it on
+/// the next fixed tick, and a replay or ghost race follows its recording instead.
+#[derive(Resource, Deref)]
+pub struct RunGameplay(pub Gameplay);
+
+/// Tuning of the run, applied as soon as it changes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Gameplay {
+    /// Distance the player runs every fixed tick.
+    pub speed: f32,
+    pub gate_spacing: f32,
+    /// Gates on the track at once.
+    pub gates: u32,
+    /// Width of the track the cursor or a finger steers across with analog control.
+    pub steering_width: f32,
+    pub fps_overlay: bool,
+    /// Added to the weight of a missed card.
+    pub miss_penalty: f32,
+    /// Multiplies the weight of a card answered right, down to 1.
+    pub hit_factor: f32,
+}
+
+impl Default for Gameplay {
+    fn default() -> Self {
+        let rules = ReviewRules::default();
+        Gameplay {
+            speed: 0.2,
+            gate_spacing: 25.,
+            gates: 3,
+            steering_width: 8.,
+            fps_overlay: true,
+            miss_penalty: rules.miss_penalty,
+            hit_factor: rules.hit_factor,
+        }
+    }
+}
+
+impl Gameplay {
+    // Ranges the settings menu tunes within, hand-edited values are clamped to them.
+    pub const SPEED: RangeInclusive<f32> = 0.02..=1.;
+    pub const GATE_SPACING: RangeInclusive<f32> = 15.0..=100.;
+    pub const GATES: RangeInclusive<u32> = 1..=8;
+    pub const STEERING_WIDTH: RangeInclusive<f32> = 2.0..=16.;
+    pub const MISS_PENALTY: RangeInclusive<f32> = 0.0..=200.;
+    pub const HIT_FACTOR: RangeInclusive<f32> = 0.05..=1.;
+
+    /// Brings every value into its range, a value that is not a number is reset
+    /// to its default.
+    fn clamp(&mut self) {
+        let defaults = Gameplay::default();
+        let clamp = |value: f32, default: f32, range: RangeInclusive<f32>| {
+            if value.is_nan() {
+                default
+            } else {
+                value.clamp(*range.start(), *range.end())
+            }
+        };
+        self.speed = clamp(self.speed, defaults.speed, Gameplay::SPEED);
+        self.gate_spacing = clamp(
+            self.gate_spacing,
+            defaults.gate_spacing,
+            Gameplay::GATE_SPACING,
+        );
+        self.gates = self
+            .gates
+            .clamp(*Gameplay::GATES.start(), *Gameplay::GATES.end());
+        self.steering_width = clamp(
+            self.steering_width,
+            defaults.steering_width,
+            Gameplay::STEERING_WIDTH,
+        );
+        self.miss_penalty = clamp(
+            self.miss_penalty,
+            defaults.miss_penalty,
+            Gameplay::MISS_PENALTY,
+        );
+        self.hit_factor = clamp(self.hit_factor, defaults.hit_factor, Gameplay::HIT_FACTOR);
+    }
+
+    pub fn review_rules(&self) -> ReviewRules {
+        ReviewRules {
+            miss_penalty: self.miss_penalty,
+            hit_factor: self.hit_factor,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings file, falling back to the defaults when there is none
+    /// or it can not be parsed.
+    pub fn load() -> Self {
+        let Ok(content) = fs::read_to_string(SETTINGS_FILE) else {
+            return Settings::default();
+        };
+        let mut settings: Settings = toml::from_str(&content).unwrap_or_else(|err| {
+            warn!("could not parse {}, using defaults: {}", SETTINGS_FILE, err);
+            Settings::default()
+        });
+        settings.input.fill_defaults();
+        let loaded = settings.gameplay;
+        settings.gameplay.clamp();
+        if settings.gameplay != loaded {
+            warn!(
+                "clamped the [gameplay] values of {} to the ranges of the settings menu",
+                SETTINGS_FILE
+            );
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let content = toml::to_string_pretty(self).expect("could not serialize settings");
+        if let Err(err) = fs::write(SETTINGS_FILE, content) {
+            warn!("could not write {}: {}", SETTINGS_FILE, err);
+        }
+    }
+}
@@ -0,0 +1,523 @@
+use bevy::color::palettes::css::*;
+use bevy::prelude::*;
+use std::ops::RangeInclusive;
+
+use crate::input::{Action, Binding, InputMap};
+use crate::session::{ControlScheme, StudySession};
+use crate::settings::{Gameplay, Settings};
+
+/// In-game menu opened with the settings action, pauses the run while open.
+/// Lists every action with its bindings, clicking one waits for a new key or
+/// button, and the gameplay settings with buttons to change them. Every change
+/// applies right away and is saved to the settings file.
+pub struct SettingsMenu;
+
+#[derive(Component)]
+pub struct SettingsMenuRoot {
+    /// Whether the run was already paused when the menu opened.
+    was_paused: bool,
+}
+
+#[derive(Component)]
+struct RebindButton(Action);
+
+#[derive(Component)]
+struct ResetButton;
+
+/// Changes a gameplay setting by `steps` of its step size.
+#[derive(Component)]
+struct AdjustButton {
+    tunable: Tunable,
+    steps: i32,
+}
+
+#[derive(Component)]
+struct FpsOverlayButton;
+
+#[derive(Component)]
+struct ControlButton;
+
+/// The numeric gameplay settings, with the step and range the menu changes them by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tunable {
+    Speed,
+    GateSpacing,
+    Gates,
+    SteeringWidth,
+    MissPenalty,
+    HitFactor,
+}
+
+impl Tunable {
+    const ALL: [Tunable; 6] = [
+        Tunable::Speed,
+        Tunable::GateSpacing,
+        Tunable::Gates,
+        Tunable::SteeringWidth,
+        Tunable::MissPenalty,
+        Tunable::HitFactor,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Tunable::Speed => "Speed",
+            Tunable::GateSpacing => "Gate spacing",
+            Tunable::Gates => "Gates ahead",
+            Tunable::SteeringWidth => "Steering width",
+            Tunable::MissPenalty => "Weight added on a miss",
+            Tunable::HitFactor => "Weight kept on a hit",
+        }
+    }
+
+    fn value(self, gameplay: &Gameplay) -> String {
+        match self {
+            Tunable::Speed => format!("{:.2}", gameplay.speed),
+            Tunable::GateSpacing => format!("{}", gameplay.gate_spacing),
+            Tunable::Gates => format!("{}", gameplay.gates),
+            Tunable::SteeringWidth => format!("{}", gameplay.steering_width),
+            Tunable::MissPenalty => format!("{}", gameplay.miss_penalty),
+            Tunable::HitFactor => format!("{:.2}", gameplay.hit_factor),
+        }
+    }
+
+    fn adjust(self, gameplay: &mut Gameplay, steps: i32) {
+        // snapped to whole steps, so repeated clicks do not drift
+        let nudge = |value: f32, step: f32, range: RangeInclusive<f32>| {
+            (((value / step).round() + steps as f32) * step).clamp(*range.start(), *range.end())
+        };
+        match self {
+            Tunable::Speed => gameplay.speed = nudge(gameplay.speed, 0.02, Gameplay::SPEED),
+            Tunable::GateSpacing => {
+                gameplay.gate_spacing = nudge(gameplay.gate_spacing, 5., Gameplay::GATE_SPACING)
+            }
+            Tunable::Gates => {
+                gameplay.gates = gameplay
+                    .gates
+                    .saturating_add_signed(steps)
+                    .clamp(*Gameplay::GATES.start(), *Gameplay::GATES.end())
+            }
+            Tunable::SteeringWidth => {
+                gameplay.steering_width =
+                    nudge(gameplay.steering_width, 1., Gameplay::STEERING_WIDTH)
+            }
+            Tunable::MissPenalty => {
+                gameplay.miss_penalty = nudge(gameplay.miss_penalty, 5., Gameplay::MISS_PENALTY)
+            }
+            Tunable::HitFactor => {
+                gameplay.hit_factor = nudge(gameplay.hit_factor, 0.05, Gameplay::HIT_FACTOR)
+            }
+        }
+    }
+}
+
+/// The action waiting for a new binding. It is armed from the frame after its
+/// button was clicked, so the click itself is not taken as the binding.
+#[derive(Resource, Default)]
+struct Rebinding {
+    action: Option<Action>,
+    armed: bool,
+}
+
+const BUTTON_COLOR: Color = Color::srgb(0.2, 0.2, 0.2);
+const BUTTON_HOVER_COLOR: Color = Color::srgb(0.35, 0.35, 0.35);
+const REBIND_HINT: &str =
+    "Click a binding, then press a key, mouse button or gamepad button. Escape cancels.";
+
+impl Plugin for SettingsMenu {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load())
+            .init_resource::<Rebinding>()
+            .add_systems(
+                Update,
+                (
+                    toggle_settings_menu,
+                    settings_menu_buttons,
+                    capture_rebinding,
+                    refresh_settings_menu,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Run condition for gameplay input that the menu takes over while it is open.
+pub fn settings_menu_closed(menu: Query<(), With<SettingsMenuRoot>>) -> bool {
+    menu.is_empty()
+}
+
+fn toggle_settings_menu(
+    mut commands: Commands,
+    actions: Res<ButtonInput<Action>>,
+    menu: Query<(Entity, &SettingsMenuRoot)>,
+    rebinding: Res<Rebinding>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    match menu.get_single() {
+        Ok((menu_id, root)) => {
+            // while waiting for a binding every key press is taken as the binding
+            if rebinding.action.is_none()
+                && actions.any_just_pressed([Action::Settings, Action::Quit])
+            {
+                commands.entity(menu_id).despawn_recursive();
+                if !root.was_paused {
+                    time.unpause();
+                }
+            }
+        }
+        Err(_) => {
+            if actions.just_pressed(Action::Settings) {
+                commands.spawn((
+                    Node {
+                        position_type: PositionType::Absolute,
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgba(0., 0., 0., 0.85)),
+                    GlobalZIndex(10),
+                    SettingsMenuRoot {
+                        was_paused: time.is_paused(),
+                    },
+                ));
+                time.pause();
+            }
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
+fn settings_menu_buttons(
+    mut interactions: Query<
+        (
+            &Interaction,
+            &mut BackgroundColor,
+            Option<&RebindButton>,
+            Option<&AdjustButton>,
+            Has<FpsOverlayButton>,
+            Has<ControlButton>,
+            Has<ResetButton>,
+        ),
+        Changed<Interaction>,
+    >,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+    mut session: ResMut<StudySession>,
+) {
+    for (interaction, mut background, rebind, adjust, fps_overlay, control, reset) in
+        &mut interactions
+    {
+        background.0 = match interaction {
+            Interaction::Hovered => BUTTON_HOVER_COLOR,
+            _ => BUTTON_COLOR,
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        if let Some(RebindButton(action)) = rebind {
+            *rebinding = Rebinding {
+                action: Some(*action),
+                armed: false,
+            };
+        }
+        if let Some(adjust) = adjust {
+            adjust.tunable.adjust(&mut settings.gameplay, adjust.steps);
+            settings.save();
+        }
+        if fps_overlay {
+            settings.gameplay.fps_overlay = !settings.gameplay.fps_overlay;
+            settings.save();
+        }
+        if control {
+            // the menu takes over from `--control` once the scheme is changed here
+            settings.control = match session.control.take().unwrap_or(settings.control) {
+                ControlScheme::Analog => ControlScheme::Lanes,
+                ControlScheme::Lanes => ControlScheme::Analog,
+            };
+            settings.save();
+        }
+        if reset {
+            settings.input = InputMap::default();
+            settings.gameplay = Gameplay::default();
+            settings.control = ControlScheme::default();
+            session.control = None;
+            settings.save();
+        }
+    }
+}
+
+/// Binds the next key, mouse button or gamepad button pressed to the action
+/// being rebound. Escape cancels.
+fn capture_rebinding(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    gamepads: Query<&Gamepad>,
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<Settings>,
+) {
+    let Some(action) = rebinding.action else {
+        return;
+    };
+    if !rebinding.armed {
+        rebinding.armed = true;
+        return;
+    }
+    if keyboard.just_pressed(KeyCode::Escape) {
+        *rebinding = Rebinding::default();
+        return;
+    }
+
+    let binding = keyboard
+        .get_just_pressed()
+        .next()
+        .map(|key| Binding::Key(*key))
+        .or_else(|| {
+            mouse
+                .get_just_pressed()
+                .next()
+                .map(|button| Binding::Mouse(*button))
+        })
+        .or_else(|| {
+            gamepads.iter().find_map(|gamepad| {
+                gamepad
+                    .get_just_pressed()
+                    .next()
+                    .map(|button| Binding::Gamepad(*button))
+            })
+        });
+    if let Some(binding) = binding {
+        settings.input.rebind(action, binding);
+        settings.save();
+        *rebinding = Rebinding::default();
+    }
+}
+
+/// Rebuilds the menu's contents when it opens and whenever a binding changes.
+fn refresh_settings_menu(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    session: Res<StudySession>,
+    rebinding: Res<Rebinding>,
+    menu: Query<(Entity, Ref<SettingsMenuRoot>)>,
+) {
+    let Ok((menu_id, root)) = menu.get_single() else {
+        return;
+    };
+    if !(root.is_added() || settings.is_changed() || rebinding.is_changed()) {
+        return;
+    }
+
+    commands
+        .entity(menu_id)
+        .despawn_descendants()
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Settings"),
+                TextFont {
+                    font_size: 40.0,
+                    ..default()
+                },
+            ));
+            parent.spawn((
+                Text::new(REBIND_HINT),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(GRAY.into()),
+            ));
+
+            parent
+                .spawn(Node {
+                    flex_direction: FlexDirection::Row,
+                    column_gap: Val::Px(48.),
+                    ..default()
+                })
+                .with_children(|columns| {
+                    columns.spawn(column_node()).with_children(|column| {
+                        for action in Action::ALL {
+                            let bindings = if rebinding.action == Some(action) {
+                                String::from("press a key or button...")
+                            } else {
+                                let bindings = settings.input.bindings(action);
+                                if bindings.is_empty() {
+                                    String::from("unbound")
+                                } else {
+                                    bindings
+                                        .iter()
+                                        .map(Binding::label)
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                }
+                            };
+
+                            column.spawn(row_node()).with_children(|row| {
+                                row.spawn(label(action.label()));
+                                row.spawn((
+                                    Button,
+                                    Node {
+                                        width: Val::Px(360.),
+                                        padding: UiRect::all(Val::Px(8.)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(BUTTON_COLOR),
+                                    RebindButton(action),
+                                ))
+                                .with_children(|button| {
+                                    button.spawn((
+                                        Text::new(bindings),
+                                        TextFont {
+                                            font_size: 20.0,
+                                            ..default()
+                                        },
+                                    ));
+                                });
+                            });
+                        }
+                    });
+
+                    columns.spawn(column_node()).with_children(|column| {
+                        for tunable in Tunable::ALL {
+                            column.spawn(row_node()).with_children(|row| {
+                                row.spawn(label(tunable.label()));
+                                adjust_button(row, tunable, -1, "-");
+                                row.spawn((
+                                    Text::new(tunable.value(&settings.gameplay)),
+                                    TextFont {
+                                        font_size: 20.0,
+                                        ..default()
+                                    },
+                                    Node {
+                                        width: Val::Px(60.),
+                                        ..default()
+                                    },
+                                    TextLayout::new_with_justify(JustifyText::Center),
+                                ));
+                                adjust_button(row, tunable, 1, "+");
+                            });
+                        }
+                        column.spawn(row_node()).with_children(|row| {
+                            row.spawn(label("FPS overlay"));
+                            toggle_button(
+                                row,
+                                if settings.gameplay.fps_overlay {
+                                    "on"
+                                } else {
+                                    "off"
+                                },
+                                FpsOverlayButton,
+                            );
+                        });
+                        column.spawn(row_node()).with_children(|row| {
+                            row.spawn(label("Control"));
+                            toggle_button(
+                                row,
+                                session.control.unwrap_or(settings.control).label(),
+                                ControlButton,
+                            );
+                        });
+                    });
+                });
+
+            parent
+                .spawn((
+                    Button,
+                    Node {
+                        margin: UiRect::top(Val::Px(12.)),
+                        padding: UiRect::all(Val::Px(8.)),
+                        ..default()
+                    },
+                    BackgroundColor(BUTTON_COLOR),
+                    ResetButton,
+                ))
+                .with_children(|button| {
+                    button.spawn((
+                        Text::new("Reset to defaults"),
+                        TextFont {
+                            font_size: 20.0,
+                            ..default()
+                        },
+                    ));
+                });
+        });
+}
+
+fn column_node() -> Node {
+    Node {
+        flex_direction: FlexDirection::Column,
+        row_gap: Val::Px(12.),
+        ..default()
+    }
+}
+
+fn row_node() -> Node {
+    Node {
+        flex_direction: FlexDirection::Row,
+        align_items: AlignItems::Center,
+        column_gap: Val::Px(20.),
+        ..default()
+    }
+}
+
+fn label(text: &str) -> (Text, TextFont, Node) {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 24.0,
+            ..default()
+        },
+        Node {
+            width: Val::Px(260.),
+            ..default()
+        },
+    )
+}
+
+fn toggle_button(row: &mut ChildBuilder, value: &str, marker: impl Component) {
+    row.spawn((
+        Button,
+        Node {
+            width: Val::Px(80.),
+            padding: UiRect::all(Val::Px(8.)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        marker,
+    ))
+    .with_children(|button| {
+        button.spawn((
+            Text::new(value),
+            TextFont {
+                font_size: 20.0,
+                ..default()
+            },
+        ));
+    });
+}
+
+fn adjust_button(row: &mut ChildBuilder, tunable: Tunable, steps: i32, text: &str) {
+    row.spawn((
+        Button,
+        Node {
+            width: Val::Px(40.),
+            padding: UiRect::all(Val::Px(8.)),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(BUTTON_COLOR),
+        AdjustButton { tunable, steps },
+    ))
+    .with_children(|button| {
+        button.spawn((
+            Text::new(text),
+            TextFont {
+                font_size: 20.0,
+                ..default()
+            },
+        ));
+    });
+}
@@ -0,0 +1,148 @@
+use bevy::asset::io::file::FileAssetReader;
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+
+use flashcard_core::pronunciation::{KanaClips, KANA_CLIP_DIR};
+use flashcard_core::CardId;
+
+use crate::decks::{Deck, DeckHandles};
+use crate::{
+    score_gate, sign_spawn_manager, DistanceTracker, GameState, Gate, GatePassed, GateState,
+    QuizState,
+};
+
+/// Pronounces the card of the next gate once the player heads for it, and a
+/// missed card again. Cards without their own `audio` clip are read out from
+/// the per-kana clips, and stay silent if their reading has a kana without one.
+pub struct Speech;
+
+impl Plugin for Speech {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Speaker>()
+            .add_systems(OnEnter(GameState::Running), index_kana_clips)
+            .add_systems(
+                FixedUpdate,
+                (
+                    say_missed_cards.after(score_gate),
+                    say_next_gate.after(sign_spawn_manager),
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(Update, play_next_clip);
+    }
+}
+
+/// The per-kana clips of the hiragana decks.
+#[derive(Resource, Deref)]
+pub struct KanaIndex(pub KanaClips);
+
+/// Clips waiting to be played one after the other.
+#[derive(Resource, Default)]
+struct Speaker {
+    queue: VecDeque<Handle<AudioSource>>,
+    /// Every clip asked for so far, kept so a missing file is only loaded once.
+    clips: HashMap<String, Handle<AudioSource>>,
+    /// The gate and card last announced.
+    announced: Option<(Entity, CardId)>,
+}
+
+impl Speaker {
+    fn say(&mut self, asset_server: &AssetServer, clips: Option<Vec<String>>) {
+        for path in clips.into_iter().flatten() {
+            let clip = self
+                .clips
+                .entry(path)
+                .or_insert_with_key(|path| asset_server.load(path.clone()));
+            self.queue.push_back(clip.clone());
+        }
+    }
+}
+
+/// The clip playing right now.
+#[derive(Component)]
+struct Voice;
+
+fn index_kana_clips(mut commands: Commands, decks: Res<Assets<Deck>>, handles: Res<DeckHandles>) {
+    // without any kana clips every card would log a missing file per kana
+    let clip_dir = FileAssetReader::get_base_path()
+        .join(AssetPlugin::default().file_path)
+        .join(KANA_CLIP_DIR);
+    if !clip_dir.is_dir() {
+        info!(
+            "{} does not exist, only cards with their own audio are pronounced",
+            clip_dir.display()
+        );
+        commands.insert_resource(KanaIndex(KanaClips::default()));
+        return;
+    }
+    let kana = handles.kana_clips(&decks).unwrap_or_else(|err| {
+        warn!("cards without audio stay silent: {}", err);
+        KanaClips::default()
+    });
+    commands.insert_resource(KanaIndex(kana));
+}
+
+fn say_missed_cards(
+    mut events: EventReader<GatePassed>,
+    quiz: Res<QuizState>,
+    kana: Res<KanaIndex>,
+    asset_server: Res<AssetServer>,
+    mut speaker: ResMut<Speaker>,
+) {
+    for event in events.read().filter(|event| !event.correct) {
+        speaker.say(&asset_server, kana.pronounce(quiz.card(event.prompt.card)));
+    }
+}
+
+fn say_next_gate(
+    player_query: Query<&Transform, With<DistanceTracker>>,
+    gate_query: Query<(Entity, &Transform, &Gate)>,
+    quiz: Res<QuizState>,
+    kana: Res<KanaIndex>,
+    asset_server: Res<AssetServer>,
+    mut speaker: ResMut<Speaker>,
+) {
+    let player_x = player_query.single().translation.x;
+    let next = gate_query
+        .iter()
+        .filter(|(_, transform, gate)| {
+            matches!(gate.gate_state, GateState::Unpass) && transform.translation.x > player_x
+        })
+        .min_by(|a, b| a.1.translation.x.total_cmp(&b.1.translation.x));
+    let Some((entity, _, gate)) = next else {
+        return;
+    };
+    // a recycled gate comes back with another card
+    if speaker.announced == Some((entity, gate.prompt.card)) {
+        return;
+    }
+    speaker.announced = Some((entity, gate.prompt.card));
+    speaker.say(&asset_server, kana.pronounce(quiz.card(gate.prompt.card)));
+}
+
+fn play_next_clip(
+    mut commands: Commands,
+    mut speaker: ResMut<Speaker>,
+    voice_query: Query<(), With<Voice>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !voice_query.is_empty() {
+        return;
+    }
+    while let Some(clip) = speaker.queue.front() {
+        match asset_server.load_state(clip) {
+            LoadState::Loaded => {
+                let clip = speaker.queue.pop_front().unwrap();
+                commands.spawn((AudioPlayer(clip), PlaybackSettings::DESPAWN, Voice));
+                return;
+            }
+            // the asset server logs the missing file, the rest still plays
+            LoadState::Failed(_) => {
+                speaker.queue.pop_front();
+            }
+            _ => return,
+        }
+    }
+}
@@ -0,0 +1,437 @@
+//! Headless runs of the gate pass logic: a bare `App` with `MinimalPlugins`,
+//! no window or renderer, stepping `FixedUpdate` by hand.
+
+use super::*;
+use crate::settings::Gameplay;
+
+fn card(word: &str, translation: &str) -> Card {
+    Card {
+        word: word.to_owned(),
+        translation: translation.to_owned(),
+        category: None,
+        reading: None,
+        lure: None,
+        audio: None,
+    }
+}
+
+fn test_app(cards: Vec<Card>) -> App {
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Assets<StandardMaterial>>()
+        .init_resource::<Replay>()
+        .insert_resource(UiInterface {
+            text_output: String::new(),
+            streak: 0,
+        })
+        .insert_resource(QuizState(Quiz::new(cards)))
+        .insert_resource(KanjiInfo(KanjiIndex::new(Vec::new())))
+        .add_event::<GatePassed>()
+        .add_systems(
+            FixedUpdate,
+            (
+                gate_pass_checker,
+                score_gate,
+                (color_gate, show_gate_feedback, record_gate),
+            )
+                .chain(),
+        );
+    app.world_mut().spawn((
+        Transform::default(),
+        Person,
+        DistanceTracker {
+            distance_traveled: 0.,
+            _distance_from_last_sign: 0.,
+        },
+    ));
+    // the camera follows 10 behind the cube and must not answer gates
+    app.world_mut()
+        .spawn((Transform::from_xyz(-10., 1.5, 0.), Person));
+    app
+}
+
+/// A prompt for some card with its answer on `side`.
+fn prompt(app: &App, rng: &mut ChaCha8Rng, side: CorrectSide) -> Prompt {
+    let mut prompt = app.world().resource::<QuizState>().next_prompt(rng);
+    if prompt.correct_index != side.choice() {
+        prompt.choices.swap(0, 1);
+        prompt.correct_index = side.choice();
+    }
+    prompt
+}
+
+fn spawn_gate(app: &mut App, prompt: Prompt, x: f32) -> Entity {
+    let material_handle = app
+        .world_mut()
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(GATE_COLOR);
+    app.world_mut()
+        .spawn((
+            Transform::from_xyz(x, -0.5, 0.),
+            Gate {
+                prompt,
+                gate_state: GateState::Unpass,
+                material_handle,
+                shown_at: Duration::ZERO,
+            },
+        ))
+        .id()
+}
+
+fn move_player_to(app: &mut App, x: f32, z: f32) {
+    let mut players = app
+        .world_mut()
+        .query_filtered::<&mut Transform, With<DistanceTracker>>();
+    let mut transform = players.single_mut(app.world_mut());
+    transform.translation.x = x;
+    transform.translation.z = z;
+}
+
+fn step(app: &mut App) {
+    app.world_mut().run_schedule(FixedUpdate);
+}
+
+fn gate_color(app: &App, gate: Entity) -> Color {
+    let handle = &app.world().get::<Gate>(gate).unwrap().material_handle;
+    app.world()
+        .resource::<Assets<StandardMaterial>>()
+        .get(handle)
+        .unwrap()
+        .base_color
+}
+
+fn ui(app: &App) -> &UiInterface {
+    app.world().resource::<UiInterface>()
+}
+
+fn quiz(app: &App) -> &Quiz {
+    app.world().resource::<QuizState>()
+}
+
+fn hiragana() -> Vec<Card> {
+    vec![card("あ", "a"), card("い", "i"), card("う", "u")]
+}
+
+#[test]
+fn right_answer_counts_towards_the_streak() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(1);
+    let prompt = prompt(&app, &mut rng, CorrectSide::Right);
+    let card = prompt.card;
+    let gate = spawn_gate(&mut app, prompt, 5.);
+
+    move_player_to(&mut app, 5., LANE_OFFSET);
+    step(&mut app);
+
+    let word = &quiz(&app).card(card).word;
+    assert_eq!(ui(&app).streak, 1);
+    assert!(ui(&app)
+        .text_output
+        .starts_with(&format!("Yes: \"{}\"", word)));
+    assert_eq!(quiz(&app).weight(card), 1.);
+    assert_eq!(gate_color(&app, gate), Color::srgb(0.2, 0.8, 0.2));
+    assert!(matches!(
+        app.world().get::<Gate>(gate).unwrap().gate_state,
+        GateState::Passed
+    ));
+}
+
+#[test]
+fn wrong_answer_resets_the_streak_and_raises_the_weight() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(2);
+    let first = prompt(&app, &mut rng, CorrectSide::Left);
+    let second = prompt(&app, &mut rng, CorrectSide::Left);
+    let missed = second.card;
+    spawn_gate(&mut app, first, 5.);
+    let gate = spawn_gate(&mut app, second, 30.);
+
+    move_player_to(&mut app, 5., -LANE_OFFSET);
+    step(&mut app);
+    assert_eq!(ui(&app).streak, 1);
+
+    move_player_to(&mut app, 30., LANE_OFFSET);
+    step(&mut app);
+    assert_eq!(ui(&app).streak, 0);
+    assert!(ui(&app).text_output.starts_with("No: "));
+    assert_eq!(quiz(&app).weight(missed), 51.);
+    assert_eq!(gate_color(&app, gate), Color::srgb(0.8, 0.2, 0.2));
+}
+
+#[test]
+fn streak_grows_over_several_gates() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(3);
+    let sides = [CorrectSide::Left, CorrectSide::Right, CorrectSide::Left];
+    for (index, side) in sides.into_iter().enumerate() {
+        let x = (index + 1) as f32 * 25.;
+        let prompt = prompt(&app, &mut rng, side);
+        spawn_gate(&mut app, prompt, x);
+        let z = match side {
+            CorrectSide::Left => -LANE_OFFSET,
+            CorrectSide::Right => LANE_OFFSET,
+        };
+        move_player_to(&mut app, x, z);
+        step(&mut app);
+    }
+    assert_eq!(ui(&app).streak, 3);
+}
+
+#[test]
+fn gate_is_not_answered_before_the_player_reaches_it() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(4);
+    let prompt = prompt(&app, &mut rng, CorrectSide::Left);
+    let gate = spawn_gate(&mut app, prompt, 5.);
+
+    move_player_to(&mut app, 4.9, -LANE_OFFSET);
+    step(&mut app);
+
+    assert_eq!(ui(&app).text_output, "");
+    assert_eq!(gate_color(&app, gate), GATE_COLOR);
+}
+
+#[test]
+fn gate_is_answered_once() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(5);
+    let prompt = prompt(&app, &mut rng, CorrectSide::Left);
+    let card = prompt.card;
+    spawn_gate(&mut app, prompt, 5.);
+
+    move_player_to(&mut app, 5., LANE_OFFSET);
+    step(&mut app);
+    // moving to the right side after passing does not change the answer
+    move_player_to(&mut app, 6., -LANE_OFFSET);
+    step(&mut app);
+
+    assert_eq!(ui(&app).streak, 0);
+    assert_eq!(quiz(&app).weight(card), 51.);
+}
+
+#[test]
+fn center_of_the_track_counts_as_left() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(6);
+    let left = prompt(&app, &mut rng, CorrectSide::Left);
+    let right = prompt(&app, &mut rng, CorrectSide::Right);
+    spawn_gate(&mut app, left, 5.);
+    spawn_gate(&mut app, right, 30.);
+
+    move_player_to(&mut app, 5., 0.);
+    step(&mut app);
+    assert_eq!(ui(&app).streak, 1);
+
+    move_player_to(&mut app, 30., f32::EPSILON);
+    step(&mut app);
+    assert_eq!(ui(&app).streak, 2);
+}
+
+/// Cards used to be looked up by word after a gate, which panicked for words
+/// missing from the list and mixed up cards sharing a word. Gates carry the
+/// card's id now, so each card keeps its own weight.
+#[test]
+fn cards_sharing_a_word_are_scored_separately() {
+    let mut app = test_app(vec![card("かみ", "paper"), card("かみ", "god")]);
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let first = prompt(&app, &mut rng, CorrectSide::Left);
+    let second = loop {
+        let prompt = prompt(&app, &mut rng, CorrectSide::Left);
+        if prompt.card != first.card {
+            break prompt;
+        }
+    };
+    let (missed, known) = (first.card, second.card);
+    spawn_gate(&mut app, first, 5.);
+    spawn_gate(&mut app, second, 30.);
+
+    move_player_to(&mut app, 5., LANE_OFFSET);
+    step(&mut app);
+    move_player_to(&mut app, 30., -LANE_OFFSET);
+    step(&mut app);
+
+    assert_eq!(quiz(&app).weight(missed), 51.);
+    assert_eq!(quiz(&app).weight(known), 1.);
+    assert!(ui(&app)
+        .text_output
+        .contains(&quiz(&app).card(known).translation));
+}
+
+#[test]
+fn passing_a_gate_sends_one_event() {
+    let mut app = test_app(hiragana());
+    let mut rng = ChaCha8Rng::seed_from_u64(8);
+    let prompt = prompt(&app, &mut rng, CorrectSide::Right);
+    let card = prompt.card;
+    let gate = spawn_gate(&mut app, prompt, 5.);
+
+    move_player_to(&mut app, 5., LANE_OFFSET);
+    step(&mut app);
+    move_player_to(&mut app, 6., LANE_OFFSET);
+    step(&mut app);
+
+    let events = app.world().resource::<Events<GatePassed>>();
+    let mut cursor = events.get_cursor();
+    let passed: Vec<_> = cursor.read(events).collect();
+    assert_eq!(passed.len(), 1);
+    assert_eq!(passed[0].gate, gate);
+    assert_eq!(passed[0].prompt.card, card);
+    assert_eq!(passed[0].chosen_lane, CorrectSide::Right);
+    assert!(passed[0].correct);
+}
+
+#[test]
+fn decks_load_as_assets_before_the_run_starts() {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin::default(),
+        bevy::state::app::StatesPlugin,
+    ))
+    .init_state::<GameState>()
+    .enable_state_scoped_entities::<GameState>()
+    .insert_resource(StudySession::default())
+    .insert_resource(UiFont(Handle::default()))
+    .add_plugins(DeckAssets);
+
+    for _ in 0..500 {
+        app.update();
+        if *app.world().resource::<State<GameState>>() == GameState::Running {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        *app.world().resource::<State<GameState>>(),
+        GameState::Running
+    );
+
+    let decks = app.world().resource::<Assets<Deck>>();
+    let handles = app.world().resource::<DeckHandles>();
+    let cards = handles
+        .cards(decks, app.world().resource::<StudySession>())
+        .unwrap();
+    assert!(cards.iter().any(|card| card.word == "あ"));
+    assert!(handles.kanji_index(decks).is_ok());
+
+    // cards without their own audio are read out from the hiragana deck's clips
+    let kana = handles.kana_clips(decks).unwrap();
+    let ka = cards.iter().find(|card| card.word == "か").unwrap();
+    assert_eq!(
+        kana.pronounce(ka),
+        Some(vec![String::from("audio/kana/ka.ogg")])
+    );
+    assert_eq!(kana.clips("カサ").map(|clips| clips.len()), Some(2));
+}
+
+#[test]
+fn recordings_keep_gameplay_changes_by_tick() {
+    let quiz = Quiz::new(hiragana());
+    let start = Gameplay::default();
+    let changed = Gameplay { gates: 5, ..start };
+
+    let mut replay = Replay::record(None, &[]);
+    replay.begin(1, &quiz, start);
+    replay.end_tick(0.);
+    replay.end_tick(0.);
+    replay.gameplay_changed(changed);
+    replay.end_tick(0.);
+
+    let path = std::env::temp_dir().join(format!("recording-{}.toml", std::process::id()));
+    replay.recording().unwrap().save(&path).unwrap();
+    let recording = Recording::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(recording.gameplay(), start);
+    assert_eq!(recording.gameplay_change(1), None);
+    assert_eq!(recording.gameplay_change(2), Some(changed));
+}
+
+#[test]
+fn a_full_glyph_atlas_starts_over_with_the_signs_shown() {
+    use ab_glyph::Font as _;
+
+    let bytes = std::fs::read("assets/MesloLGS NF Regular.ttf").unwrap();
+    let font = ab_glyph::FontRef::try_from_slice(&bytes).unwrap();
+    // more glyphs with an outline than fit in the atlas
+    let mut chars: Vec<char> = font
+        .codepoint_ids()
+        .filter(|(_, c)| !c.is_control())
+        .filter(|(id, _)| font.outline_glyph(id.with_scale(64.)).is_some())
+        .map(|(_, c)| c)
+        .collect();
+    chars.sort();
+    assert!(chars.len() > 3000, "{}", chars.len());
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .init_resource::<Assets<Image>>()
+        .init_resource::<Assets<Mesh>>()
+        .init_resource::<Assets<Font>>()
+        .init_resource::<Assets<StandardMaterial>>()
+        .insert_resource(StudySession {
+            sign_backend: SignBackend::GlyphAtlas,
+            ..default()
+        });
+    let font = app
+        .world_mut()
+        .resource_mut::<Assets<Font>>()
+        .add(Font::try_from_bytes(bytes.clone()).unwrap());
+    app.insert_resource(UiFont(font)).add_plugins(GlyphSigns);
+
+    let mut sign = None;
+    for line in chars.chunks(30) {
+        if let Some(sign) = sign {
+            app.world_mut().despawn(sign);
+        }
+        let text: String = line.iter().collect();
+        let mesh = app
+            .world_mut()
+            .resource_mut::<Assets<Mesh>>()
+            .add(Cuboid::default());
+        sign = Some(
+            app.world_mut()
+                .spawn((
+                    GlyphText::new(
+                        None,
+                        vec![RubySegment {
+                            base: text,
+                            ruby: None,
+                        }],
+                    ),
+                    Mesh3d(mesh),
+                ))
+                .id(),
+        );
+        app.update();
+
+        // every character of the sign shown is drawn, even after the atlas filled up
+        let mesh = app.world().get::<Mesh3d>(sign.unwrap()).unwrap();
+        let positions = app
+            .world()
+            .resource::<Assets<Mesh>>()
+            .get(&mesh.0)
+            .unwrap()
+            .attribute(Mesh::ATTRIBUTE_POSITION)
+            .unwrap()
+            .len();
+        assert_eq!(positions, line.len() * 4);
+    }
+}
+
+#[test]
+fn the_run_systems_wait_for_the_decks() {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, bevy::state::app::StatesPlugin))
+        .init_state::<GameState>()
+        .add_plugins((Track, GameUI, GhostRace, Speech));
+
+    // nothing of the run exists while the decks load
+    for _ in 0..5 {
+        app.update();
+    }
+    assert_eq!(
+        *app.world().resource::<State<GameState>>(),
+        GameState::Loading
+    );
+}